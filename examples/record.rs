@@ -0,0 +1,21 @@
+use screenshots::{
+  yuv::{Matrix, Range, Y4mWriter},
+  RecordOptions, Screen,
+};
+use std::io;
+
+// cargo run --example record | ffmpeg -i - -colorspace bt709 target/record.mp4
+fn main() {
+  let screen = Screen::from_point(100, 100).unwrap();
+  let recording = screen
+    .record(RecordOptions {
+      fps: 30.0,
+      max_frames: Some(150),
+      ..Default::default()
+    })
+    .unwrap();
+
+  let mut writer =
+    Y4mWriter::new(io::stdout().lock(), 30.0, Matrix::Bt709, Range::Limited).unwrap();
+  writer.write_recording(recording).unwrap();
+}
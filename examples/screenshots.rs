@@ -1,5 +1,5 @@
 use screenshots::Screen;
-use std::{fs, time::Instant};
+use std::time::Instant;
 
 fn main() {
   let start = Instant::now();
@@ -8,20 +8,21 @@ fn main() {
   for screen in screens {
     println!("capturer {screen:?}");
     let mut image = screen.capture().unwrap();
-    let mut buffer = image.buffer();
-    fs::write(format!("target/{}.png", screen.display_info.id), buffer).unwrap();
+    image
+      .save(format!("target/{}.png", screen.display_info.id))
+      .unwrap();
 
     image = screen.capture_area(300, 300, 300, 300).unwrap();
-    buffer = image.buffer();
-    fs::write(format!("target/{}-2.png", screen.display_info.id), buffer).unwrap();
+    image
+      .save(format!("target/{}-2.png", screen.display_info.id))
+      .unwrap();
   }
 
   let screen = Screen::from_point(100, 100).unwrap();
   println!("capturer {screen:?}");
 
   let image = screen.capture_area(300, 300, 300, 300).unwrap();
-  let buffer = image.buffer();
-  fs::write("target/capture_display_with_point.png", buffer).unwrap();
+  image.save("target/capture_display_with_point.png").unwrap();
 
   println!("运行耗时: {:?}", start.elapsed());
 }
@@ -0,0 +1,96 @@
+use crate::{encoder, AnimationFormat, Image};
+use anyhow::{anyhow, Result};
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+  time::Duration,
+};
+
+/// A sequence of same-sized images, e.g. from repeated `Screen::capture_area`
+/// calls, encoded as APNG or GIF. Only the changed part of each frame is
+/// stored, unchanged frames extend the previous one.
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+  frames: Vec<(Image, Duration)>,
+  plays: u16,
+}
+
+impl Animation {
+  pub fn new() -> Self {
+    Animation::default()
+  }
+
+  /// Appends `image`, shown for `delay` before the next frame.
+  pub fn frame(mut self, image: Image, delay: Duration) -> Self {
+    self.push(image, delay);
+    self
+  }
+
+  pub fn push(&mut self, image: Image, delay: Duration) {
+    self.frames.push((image, delay));
+  }
+
+  /// Number of times the animation plays, `0` (default) loops forever.
+  pub fn plays(mut self, plays: u16) -> Self {
+    self.plays = plays;
+    self
+  }
+
+  pub fn len(&self) -> usize {
+    self.frames.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.frames.is_empty()
+  }
+
+  /// Encodes the animation in the given format.
+  pub fn encode(&self, format: AnimationFormat) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    self.write_to(&mut buffer, format)?;
+    Ok(buffer)
+  }
+
+  pub fn write_to<W: Write>(&self, w: &mut W, format: AnimationFormat) -> Result<()> {
+    let (first, _) = self
+      .frames
+      .first()
+      .ok_or_else(|| anyhow!("Animation has no frames"))?;
+    if first.width() == 0 || first.height() == 0 {
+      return Err(anyhow!("Animation frames are empty"));
+    }
+
+    for (image, _) in &self.frames {
+      if image.width() != first.width() || image.height() != first.height() {
+        return Err(anyhow!(
+          "Frame size mismatch: {}x{} and {}x{}",
+          first.width(),
+          first.height(),
+          image.width(),
+          image.height()
+        ));
+      }
+    }
+
+    encoder::write_animation(&self.frames, w, format, self.plays)
+  }
+
+  /// Saves the animation, inferring the format from the file extension
+  /// (`.png`/`.apng` or `.gif`).
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let format = AnimationFormat::from_path(path)
+      .ok_or_else(|| anyhow!("Unsupported animation format: {}", path.display()))?;
+
+    self.save_with_format(path, format)
+  }
+
+  pub fn save_with_format<P: AsRef<Path>>(&self, path: P, format: AnimationFormat) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    self.write_to(&mut writer, format)?;
+    writer.flush()?;
+
+    Ok(())
+  }
+}
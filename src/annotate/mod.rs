@@ -0,0 +1,366 @@
+//! Drawing primitives for marking up captures.
+//!
+//! Coordinates are in image pixels, `(x, y)` being the center of that pixel.
+//! Shapes are antialiased and blended over the existing pixels, so colors
+//! with an alpha below `255` are translucent.
+
+mod font;
+
+use crate::{Image, Rect};
+
+/// RGBA color.
+pub type Color = [u8; 4];
+
+/// Outline color and width of a shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+  pub color: Color,
+  pub width: f32,
+}
+
+impl Stroke {
+  pub fn new(color: Color, width: f32) -> Self {
+    Stroke { color, width }
+  }
+}
+
+/// Appearance of [`text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+  pub color: Color,
+  /// Integer scale of the 8x8 font.
+  pub scale: u32,
+  /// Box drawn behind the text, if any.
+  pub background: Option<Color>,
+  /// Space between the text and the edge of the background box.
+  pub padding: u32,
+}
+
+impl Default for TextStyle {
+  fn default() -> Self {
+    TextStyle {
+      color: [255, 255, 255, 255],
+      scale: 2,
+      background: Some([0, 0, 0, 160]),
+      padding: 4,
+    }
+  }
+}
+
+// 源颜色按覆盖率混合到目标像素上 (source-over)
+pub(crate) fn blend(pixel: &mut [u8], color: Color, coverage: f32) {
+  let alpha = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+  if alpha <= 0.0 {
+    return;
+  }
+
+  let dst_alpha = pixel[3] as f32 / 255.0;
+  let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+  for c in 0..3 {
+    let value = (color[c] as f32 * alpha + pixel[c] as f32 * dst_alpha * (1.0 - alpha)) / out_alpha;
+    pixel[c] = value.round().clamp(0.0, 255.0) as u8;
+  }
+  pixel[3] = (out_alpha * 255.0).round() as u8;
+}
+
+/// Blends `color` over every pixel in the box `min..=max`, weighted by
+/// `coverage(x, y)`.
+fn paint(
+  image: &mut Image,
+  min: (f32, f32),
+  max: (f32, f32),
+  color: Color,
+  coverage: impl Fn(f32, f32) -> f32,
+) {
+  if image.width() == 0 || image.height() == 0 {
+    return;
+  }
+
+  let x0 = min.0.floor().max(0.0) as u32;
+  let y0 = min.1.floor().max(0.0) as u32;
+  let x1 = (max.0.ceil().max(-1.0) as i64).min(image.width() as i64 - 1);
+  let y1 = (max.1.ceil().max(-1.0) as i64).min(image.height() as i64 - 1);
+  if x1 < x0 as i64 || y1 < y0 as i64 {
+    return;
+  }
+
+  let stride = image.stride();
+  let buffer = image.as_raw_mut();
+  for y in y0..=y1 as u32 {
+    for x in x0..=x1 as u32 {
+      let c = coverage(x as f32, y as f32);
+      if c > 0.0 {
+        let i = y as usize * stride + x as usize * 4;
+        blend(&mut buffer[i..i + 4], color, c);
+      }
+    }
+  }
+}
+
+// 距离转换为覆盖率，边缘过渡一个像素
+fn stroke_coverage(distance: f32, width: f32) -> f32 {
+  (width / 2.0 - distance + 0.5).clamp(0.0, 1.0)
+}
+
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+  let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+  let len2 = dx * dx + dy * dy;
+  let t = if len2 > 0.0 {
+    (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+  } else {
+    0.0
+  };
+  ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+fn bounds(points: &[(f32, f32)], margin: f32) -> ((f32, f32), (f32, f32)) {
+  points.iter().fold(
+    ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN)),
+    |(min, max), p| {
+      (
+        (min.0.min(p.0 - margin), min.1.min(p.1 - margin)),
+        (max.0.max(p.0 + margin), max.1.max(p.1 + margin)),
+      )
+    },
+  )
+}
+
+/// Draws a straight line with round caps.
+pub fn line(image: &mut Image, from: (f32, f32), to: (f32, f32), stroke: Stroke) {
+  polyline(image, &[from, to], stroke);
+}
+
+/// Draws connected line segments, e.g. a freehand stroke. Overlapping
+/// segments are blended once, so translucent strokes stay even.
+pub fn polyline(image: &mut Image, points: &[(f32, f32)], stroke: Stroke) {
+  if points.is_empty() || image.width() == 0 || image.height() == 0 {
+    return;
+  }
+
+  let margin = stroke.width / 2.0 + 1.0;
+  let (min, max) = bounds(points, margin);
+  let x0 = min.0.floor().max(0.0) as i64;
+  let y0 = min.1.floor().max(0.0) as i64;
+  let x1 = (max.0.ceil() as i64).min(image.width() as i64 - 1);
+  let y1 = (max.1.ceil() as i64).min(image.height() as i64 - 1);
+  if x1 < x0 || y1 < y0 {
+    return;
+  }
+
+  let segments: Vec<_> = if points.len() == 1 {
+    vec![(points[0], points[0])]
+  } else {
+    points.windows(2).map(|w| (w[0], w[1])).collect()
+  };
+
+  // 每个线段只计算自身附近的像素，覆盖率取最大值后统一混合
+  let box_width = (x1 - x0 + 1) as usize;
+  let mut coverage = vec![0u8; box_width * (y1 - y0 + 1) as usize];
+  for &(a, b) in &segments {
+    let (min, max) = bounds(&[a, b], margin);
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    // 距线段 margin 以内的点都在同宽的无限长条带内，按行求条带的横向范围
+    let half = margin * dx.hypot(dy) / dy.abs();
+
+    for y in (min.1.floor() as i64).max(y0)..=(max.1.ceil() as i64).min(y1) {
+      let (mut left, mut right) = (min.0, max.0);
+      if half.is_finite() {
+        let center = a.0 + (y as f32 - a.1) * dx / dy;
+        left = left.max(center - half);
+        right = right.min(center + half);
+      }
+
+      let row = (y - y0) as usize * box_width;
+      for x in (left.floor() as i64).max(x0)..=(right.ceil() as i64).min(x1) {
+        let c = stroke_coverage(segment_distance((x as f32, y as f32), a, b), stroke.width);
+        let cell = &mut coverage[row + (x - x0) as usize];
+        *cell = (*cell).max((c * 255.0).round() as u8);
+      }
+    }
+  }
+
+  let stride = image.stride();
+  let buffer = image.as_raw_mut();
+  for (row, y) in coverage.chunks_exact(box_width).zip(y0 as usize..) {
+    for (&c, x) in row.iter().zip(x0 as usize..) {
+      if c > 0 {
+        let i = y * stride + x * 4;
+        blend(&mut buffer[i..i + 4], stroke.color, c as f32 / 255.0);
+      }
+    }
+  }
+}
+
+/// Draws the outline of `rect`, centered on its outermost pixels.
+pub fn rectangle(image: &mut Image, rect: Rect, stroke: Stroke) {
+  if rect.is_empty() {
+    return;
+  }
+
+  let (x0, y0) = (rect.x as f32, rect.y as f32);
+  let (x1, y1) = ((rect.right() - 1) as f32, (rect.bottom() - 1) as f32);
+  let center = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+  let half = ((x1 - x0) / 2.0, (y1 - y0) / 2.0);
+  let margin = stroke.width / 2.0 + 1.0;
+
+  paint(
+    image,
+    (x0 - margin, y0 - margin),
+    (x1 + margin, y1 + margin),
+    stroke.color,
+    |x, y| {
+      // 到矩形边框的有向距离
+      let qx = (x - center.0).abs() - half.0;
+      let qy = (y - center.1).abs() - half.1;
+      let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+      let distance = outside + qx.max(qy).min(0.0);
+      stroke_coverage(distance.abs(), stroke.width)
+    },
+  );
+}
+
+/// Draws the outline of the ellipse inscribed in `rect`.
+pub fn ellipse(image: &mut Image, rect: Rect, stroke: Stroke) {
+  if rect.is_empty() {
+    return;
+  }
+
+  let (x0, y0) = (rect.x as f32, rect.y as f32);
+  let (x1, y1) = ((rect.right() - 1) as f32, (rect.bottom() - 1) as f32);
+  let center = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+  let a = ((x1 - x0) / 2.0).max(0.5);
+  let b = ((y1 - y0) / 2.0).max(0.5);
+  let margin = stroke.width / 2.0 + 1.0;
+
+  paint(
+    image,
+    (x0 - margin, y0 - margin),
+    (x1 + margin, y1 + margin),
+    stroke.color,
+    |x, y| {
+      // 隐函数值除以梯度长度，近似到椭圆的距离
+      let (dx, dy) = (x - center.0, y - center.1);
+      let f = (dx / a).powi(2) + (dy / b).powi(2) - 1.0;
+      let gradient = 2.0 * ((dx / (a * a)).powi(2) + (dy / (b * b)).powi(2)).sqrt();
+      let distance = if gradient > f32::EPSILON {
+        f / gradient
+      } else {
+        a.min(b)
+      };
+      stroke_coverage(distance.abs(), stroke.width)
+    },
+  );
+}
+
+/// Draws a line from `from` to `to` with a filled arrowhead at `to`.
+pub fn arrow(image: &mut Image, from: (f32, f32), to: (f32, f32), stroke: Stroke) {
+  let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+  let length = (dx * dx + dy * dy).sqrt();
+  if length <= f32::EPSILON {
+    return;
+  }
+
+  let (ux, uy) = (dx / length, dy / length);
+  let head = (stroke.width * 4.0).max(10.0).min(length);
+  let base = (to.0 - ux * head, to.1 - uy * head);
+  let half = head / 2.0;
+  let left = (base.0 - uy * half, base.1 + ux * half);
+  let right = (base.0 + uy * half, base.1 - ux * half);
+  // 箭杆只画到箭头中部，避免端帽从箭头两侧露出
+  let shaft_end = (to.0 - ux * head * 0.6, to.1 - uy * head * 0.6);
+
+  let triangle = [to, left, right];
+  let orientation = (left.0 - to.0) * (right.1 - to.1) - (left.1 - to.1) * (right.0 - to.0);
+  let edges: Vec<_> = (0..3)
+    .map(|i| {
+      let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+      let (ex, ey) = (b.0 - a.0, b.1 - a.1);
+      let len = (ex * ex + ey * ey).sqrt();
+      // 朝外的单位法向量
+      let normal = if orientation > 0.0 {
+        (ey / len, -ex / len)
+      } else {
+        (-ey / len, ex / len)
+      };
+      (a, normal)
+    })
+    .collect();
+
+  let (min, max) = bounds(&[from, to, left, right], stroke.width / 2.0 + 1.0);
+  paint(image, min, max, stroke.color, |x, y| {
+    let shaft = stroke_coverage(segment_distance((x, y), from, shaft_end), stroke.width);
+    let distance = edges
+      .iter()
+      .map(|&(a, n)| (x - a.0) * n.0 + (y - a.1) * n.1)
+      .fold(f32::MIN, f32::max);
+    shaft.max((0.5 - distance).clamp(0.0, 1.0))
+  });
+}
+
+/// Fills `rect` with `color`, typically translucent to highlight an area.
+pub fn highlight(image: &mut Image, rect: Rect, color: Color) {
+  let bounds = Rect::new(0, 0, image.width(), image.height());
+  let Some(rect) = rect.intersection(&bounds) else {
+    return;
+  };
+
+  let stride = image.stride();
+  let buffer = image.as_raw_mut();
+  for y in rect.y as usize..rect.bottom() as usize {
+    let row = &mut buffer[y * stride + rect.x as usize * 4..y * stride + rect.right() as usize * 4];
+    for pixel in row.chunks_exact_mut(4) {
+      blend(pixel, color, 1.0);
+    }
+  }
+}
+
+/// Size in pixels of `text` rendered by [`text`] at `scale`, without padding.
+pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+  let lines = text.split('\n');
+  let (columns, rows) = lines.fold((0, 0), |(columns, rows), line| {
+    (columns.max(line.chars().count() as u32), rows + 1)
+  });
+  let size = font::GLYPH_SIZE * scale.max(1);
+  (columns * size, rows * size)
+}
+
+/// Renders `text` with its top left corner at `(x, y)` using the embedded
+/// 8x8 font. `\n` starts a new line, non-ASCII characters render as `?`.
+pub fn text(image: &mut Image, x: i32, y: i32, text: &str, style: &TextStyle) {
+  let scale = style.scale.max(1);
+  let size = (font::GLYPH_SIZE * scale) as i32;
+
+  if let Some(background) = style.background {
+    let (width, height) = text_size(text, scale);
+    let padding = style.padding;
+    let rect = Rect::new(
+      x - padding as i32,
+      y - padding as i32,
+      width + padding * 2,
+      height + padding * 2,
+    );
+    highlight(image, rect, background);
+  }
+
+  for (row, line) in text.split('\n').enumerate() {
+    for (column, c) in line.chars().enumerate() {
+      let glyph = font::glyph(c);
+      let left = x + column as i32 * size;
+      let top = y + row as i32 * size;
+
+      for (gy, bits) in glyph.iter().enumerate() {
+        for gx in 0..font::GLYPH_SIZE {
+          if bits & (1 << gx) != 0 {
+            let rect = Rect::new(
+              left + (gx * scale) as i32,
+              top + gy as i32 * scale as i32,
+              scale,
+              scale,
+            );
+            highlight(image, rect, style.color);
+          }
+        }
+      }
+    }
+  }
+}
@@ -1,9 +1,45 @@
-use crate::{DisplayInfo, Image};
+use crate::{window::WindowInfo, ColorProfile, DisplayInfo, Image, Rect, WindowRule};
 use anyhow::{anyhow, Result};
+use core_foundation::{
+  base::TCFType,
+  data::{CFData, CFDataRef},
+};
 use core_graphics::{
   display::{kCGNullWindowID, kCGWindowImageDefault, kCGWindowListOptionOnScreenOnly, CGDisplay},
-  geometry::{CGPoint, CGSize},
+  geometry::{CGPoint, CGRect, CGSize},
+  image::CGImage,
+  sys::CGColorSpaceRef,
 };
+use foreign_types::ForeignType;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+  fn CGColorSpaceCopyICCData(space: CGColorSpaceRef) -> CFDataRef;
+}
+
+fn icc_profile(cg_image: &CGImage) -> Option<Vec<u8>> {
+  let color_space = cg_image.color_space();
+  let data = unsafe { CGColorSpaceCopyICCData(color_space.as_ptr()) };
+  if data.is_null() {
+    return None;
+  }
+
+  let data = unsafe { CFData::wrap_under_create_rule(data) };
+  Some(data.bytes().to_vec())
+}
+
+fn to_image(cg_image: &CGImage) -> Result<Image> {
+  let mut image = Image::from_bgra(
+    Vec::from(cg_image.data().bytes()),
+    cg_image.width() as u32,
+    cg_image.height() as u32,
+    cg_image.bytes_per_row(),
+  )?;
+  image.metadata_mut().backend = Some("core-graphics".to_string());
+  image.set_color_profile(icc_profile(cg_image).map(ColorProfile::Icc));
+
+  Ok(image)
+}
 
 pub fn capture_screen(display_info: &DisplayInfo) -> Result<Image> {
   let cg_display = CGDisplay::new(display_info.id);
@@ -15,14 +51,51 @@ pub fn capture_screen(display_info: &DisplayInfo) -> Result<Image> {
   )
   .ok_or_else(|| anyhow!("Screen:{} screenshot failed", display_info.id))?;
 
-  let image = Image::from_bgra(
-    Vec::from(cg_image.data().bytes()),
-    cg_image.width() as u32,
-    cg_image.height() as u32,
-    cg_image.bytes_per_row(),
-  )?;
+  to_image(&cg_image)
+}
 
-  Ok(image)
+/// Repeated captures of one area. CoreGraphics keeps no per-capture state,
+/// so only the area is computed once.
+pub struct Recorder {
+  display_id: u32,
+  cg_rect: CGRect,
+}
+
+impl Recorder {
+  pub fn new(display_info: &DisplayInfo, x: i32, y: i32, width: u32, height: u32) -> Result<Self> {
+    let cg_display = CGDisplay::new(display_info.id);
+    let mut cg_rect = cg_display.bounds();
+    let origin = cg_rect.origin;
+
+    let rect_x = origin.x + (x as f64);
+    let rect_y = origin.y + (y as f64);
+    let rect_width = width as f64;
+    let rect_height = height as f64;
+
+    cg_rect.origin = CGPoint::new(rect_x, rect_y);
+    cg_rect.size = CGSize::new(rect_width, rect_height);
+
+    Ok(Recorder {
+      display_id: display_info.id,
+      cg_rect,
+    })
+  }
+
+  pub fn capture(&mut self) -> Result<Image> {
+    let cg_image = CGDisplay::screenshot(
+      self.cg_rect,
+      kCGWindowListOptionOnScreenOnly,
+      kCGNullWindowID,
+      kCGWindowImageDefault,
+    )
+    .ok_or_else(|| anyhow!("Screen:{} screenshot failed", self.display_id))?;
+
+    to_image(&cg_image)
+  }
+
+  pub fn capture_with_windows(&mut self) -> Result<(Image, Vec<WindowInfo>)> {
+    Err(anyhow!("Listing windows is not supported on this platform"))
+  }
 }
 
 pub fn capture_screen_area(
@@ -32,32 +105,24 @@ pub fn capture_screen_area(
   width: u32,
   height: u32,
 ) -> Result<Image> {
-  let cg_display = CGDisplay::new(display_info.id);
-  let mut cg_rect = cg_display.bounds();
-  let origin = cg_rect.origin;
-
-  let rect_x = origin.x + (x as f64);
-  let rect_y = origin.y + (y as f64);
-  let rect_width = width as f64;
-  let rect_height = height as f64;
-
-  cg_rect.origin = CGPoint::new(rect_x, rect_y);
-  cg_rect.size = CGSize::new(rect_width, rect_height);
-
-  let cg_image = CGDisplay::screenshot(
-    cg_rect,
-    kCGWindowListOptionOnScreenOnly,
-    kCGNullWindowID,
-    kCGWindowImageDefault,
-  )
-  .ok_or_else(|| anyhow!("Screen:{} screenshot failed", display_info.id))?;
+  Recorder::new(display_info, x, y, width, height)?.capture()
+}
 
-  let image = Image::from_bgra(
-    Vec::from(cg_image.data().bytes()),
-    cg_image.width() as u32,
-    cg_image.height() as u32,
-    cg_image.bytes_per_row(),
-  )?;
+pub fn capture_screen_area_with_windows(
+  _display_info: &DisplayInfo,
+  _x: i32,
+  _y: i32,
+  _width: u32,
+  _height: u32,
+) -> Result<(Image, Vec<WindowInfo>)> {
+  Err(anyhow!("Listing windows is not supported on this platform"))
+}
 
-  Ok(image)
+pub fn capture_raw_window(
+  _rule: &WindowRule,
+  _keep_alpha: bool,
+) -> Result<(Image, Rect, Vec<WindowInfo>)> {
+  Err(anyhow!(
+    "Capturing windows is not supported on this platform"
+  ))
 }
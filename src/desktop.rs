@@ -0,0 +1,181 @@
+use crate::{capture_raw, CaptureOptions, ColorProfile, DisplayInfo, Filter, Image, Rect, Screen};
+use anyhow::{anyhow, Result};
+
+/// How to pick the output scale when screens have different `scale_factor`s.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScalePolicy {
+  /// Use the largest scale factor, upscaling lower density screens.
+  #[default]
+  Highest,
+  /// Use the smallest scale factor, downscaling higher density screens.
+  Lowest,
+  /// Use a fixed scale factor, `1.0` gives logical pixels.
+  Fixed(f32),
+}
+
+impl ScalePolicy {
+  pub(crate) fn resolve(&self, screens: &[Screen]) -> f32 {
+    let factors = screens.iter().map(|s| s.display_info.scale_factor);
+    let scale = match self {
+      ScalePolicy::Highest => factors.fold(f32::MIN, f32::max),
+      ScalePolicy::Lowest => factors.fold(f32::MAX, f32::min),
+      ScalePolicy::Fixed(scale) => *scale,
+    };
+
+    if scale.is_finite() && scale > 0.0 {
+      scale
+    } else {
+      1.0
+    }
+  }
+}
+
+/// Logical bounds of a screen in desktop coordinates.
+pub(crate) fn screen_bounds(screen: &Screen) -> Rect {
+  let info = screen.display_info;
+  Rect::new(info.x, info.y, info.width, info.height)
+}
+
+/// Scales `rect`, relative to `origin`, snapping edges so that neighbouring
+/// rectangles stay seamless.
+fn scale_rect(rect: Rect, origin: Rect, scale: f32) -> Rect {
+  let edge = |v: i32, o: i32| ((v - o) as f32 * scale).round() as i32;
+  let x = edge(rect.x, origin.x);
+  let y = edge(rect.y, origin.y);
+
+  Rect::new(
+    x,
+    y,
+    (edge(rect.right(), origin.x) - x) as u32,
+    (edge(rect.bottom(), origin.y) - y) as u32,
+  )
+}
+
+/// Resamples a capture to the given physical size.
+pub(crate) fn scale_to(image: Image, width: u32, height: u32) -> Image {
+  if image.width() == width && image.height() == height {
+    return image;
+  }
+
+  let factor = image.width() / width.max(1);
+  if factor >= 2 && width * factor == image.width() && height * factor == image.height() {
+    image.downscale(factor)
+  } else {
+    image.resize(width, height, Filter::CatmullRom)
+  }
+}
+
+// 拼接后的图片不属于单个屏幕，时间取第一个屏幕的截图时间
+// 所有屏幕的配置文件相同时保留，否则按 sRGB 标记
+fn merge_metadata(target: &mut Image, part: &Image, area: Rect, scale: f32) {
+  let profile = match target.color_profile() {
+    None => part.color_profile().cloned(),
+    Some(profile) if part.color_profile() == Some(profile) => Some(profile.clone()),
+    Some(_) => Some(ColorProfile::Srgb),
+  };
+  target.set_color_profile(profile);
+
+  let part = part.metadata();
+  let metadata = target.metadata_mut();
+  metadata.timestamp = metadata.timestamp.or(part.timestamp);
+  metadata.backend = metadata.backend.take().or_else(|| part.backend.clone());
+  metadata.area = Some(area);
+  metadata.scale_factor = Some(scale);
+}
+
+// 水印在拼接后的图片上只加一次，显示器 id 取第一个屏幕，缩放取输出缩放
+fn stitched_info(screens: &[Screen], scale: f32) -> DisplayInfo {
+  DisplayInfo {
+    scale_factor: scale,
+    ..screens[0].display_info
+  }
+}
+
+/// Captures every screen and places each one at its `DisplayInfo` position
+/// in a single image. Areas not covered by any screen are transparent.
+pub fn capture_desktop() -> Result<Image> {
+  capture_desktop_with(ScalePolicy::default(), &CaptureOptions::default())
+}
+
+pub fn capture_desktop_with(policy: ScalePolicy, options: &CaptureOptions) -> Result<Image> {
+  options.check_stitched()?;
+  let screens = Screen::all()?;
+  let bounds = screens
+    .iter()
+    .map(screen_bounds)
+    .reduce(|a, b| a.union(&b))
+    .ok_or_else(|| anyhow!("Not found screen"))?;
+  let scale = policy.resolve(&screens);
+
+  let size = scale_rect(bounds, bounds, scale);
+  let mut desktop = Image::new(
+    size.width,
+    size.height,
+    vec![0; size.width as usize * size.height as usize * 4],
+  );
+
+  for screen in &screens {
+    let info = screen.display_info;
+    let area = Rect::new(0, 0, info.width, info.height);
+    let (image, windows) = capture_raw(&info, None, options)?;
+    let image = options.apply_stitched(image, &windows, &info, area)?;
+    merge_metadata(&mut desktop, &image, bounds, scale);
+
+    let target = scale_rect(screen_bounds(screen), bounds, scale);
+    let image = scale_to(image, target.width, target.height);
+    desktop.paste(&image, target.x, target.y);
+  }
+
+  options.finish(&mut desktop, &stitched_info(&screens, scale), scale)?;
+  Ok(desktop)
+}
+
+/// Captures an area in global desktop coordinates, which may span several
+/// screens. Parts of the area not covered by any screen are transparent.
+pub fn capture_rect(rect: Rect) -> Result<Image> {
+  capture_rect_with(rect, ScalePolicy::default(), &CaptureOptions::default())
+}
+
+/// Same as [`capture_rect`], `policy` only considers the screens the area touches.
+pub fn capture_rect_with(
+  rect: Rect,
+  policy: ScalePolicy,
+  options: &CaptureOptions,
+) -> Result<Image> {
+  options.check_stitched()?;
+  let parts: Vec<(Screen, Rect)> = Screen::all()?
+    .into_iter()
+    .filter_map(|screen| {
+      let part = screen_bounds(&screen).intersection(&rect)?;
+      Some((screen, part))
+    })
+    .collect();
+
+  if parts.is_empty() {
+    return Err(anyhow!("Area size is invalid"));
+  }
+
+  let screens: Vec<Screen> = parts.iter().map(|(screen, _)| *screen).collect();
+  let scale = policy.resolve(&screens);
+  let size = scale_rect(rect, rect, scale);
+  let mut image = Image::new(
+    size.width,
+    size.height,
+    vec![0; size.width as usize * size.height as usize * 4],
+  );
+
+  for (screen, part) in &parts {
+    let info = screen.display_info;
+    let area = Rect::new(part.x - info.x, part.y - info.y, part.width, part.height);
+    let (capture, windows) = capture_raw(&info, Some(area), options)?;
+    let capture = options.apply_stitched(capture, &windows, &info, area)?;
+    merge_metadata(&mut image, &capture, rect, scale);
+
+    let target = scale_rect(*part, rect, scale);
+    let capture = scale_to(capture, target.width, target.height);
+    image.paste(&capture, target.x, target.y);
+  }
+
+  options.finish(&mut image, &stitched_info(&screens, scale), scale)?;
+  Ok(image)
+}
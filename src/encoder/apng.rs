@@ -0,0 +1,41 @@
+use super::{frame_regions, saturating_delay};
+use crate::{ColorProfile, Image};
+use anyhow::Result;
+use png::{BitDepth, BlendOp, ColorType, DisposeOp, Encoder, Info, SrgbRenderingIntent};
+use std::{borrow::Cow, io::Write, time::Duration};
+
+pub fn write<W: Write>(frames: &[(Image, Duration)], w: &mut W, plays: u16) -> Result<()> {
+  let first = &frames[0].0;
+  let pixels: Vec<&[u8]> = frames.iter().map(|(image, _)| image.as_raw()).collect();
+  let regions = frame_regions(&pixels, frames, 4);
+
+  let mut info = Info::with_size(first.width(), first.height());
+  if let Some(ColorProfile::Icc(profile)) = first.color_profile() {
+    info.icc_profile = Some(Cow::Borrowed(profile));
+  }
+
+  let mut encoder = Encoder::with_info(w, info)?;
+  if first.color_profile() == Some(&ColorProfile::Srgb) {
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+  }
+  encoder.set_color(ColorType::Rgba);
+  encoder.set_depth(BitDepth::Eight);
+  encoder.set_animated(regions.len() as u32, plays as u32)?;
+
+  let mut writer = encoder.write_header()?;
+  for region in regions {
+    let rect = region.rect;
+    // 先归零位置，否则缩小尺寸前的越界检查会失败
+    writer.reset_frame_position()?;
+    writer.set_frame_dimension(rect.width, rect.height)?;
+    writer.set_frame_position(rect.x as u32, rect.y as u32)?;
+    writer.set_frame_delay(saturating_delay(region.delay.as_millis()), 1000)?;
+    // 变化区域直接覆盖画布，其余部分保留上一帧
+    writer.set_blend_op(BlendOp::Source)?;
+    writer.set_dispose_op(DisposeOp::None)?;
+    writer.write_image_data(frames[region.index].0.crop(rect)?.as_raw())?;
+  }
+  writer.finish()?;
+
+  Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::Image;
+use anyhow::{anyhow, Result};
+use ravif::{Encoder, Img, RGBA8};
+use std::io::Write;
+
+// rav1e 只能编码到内存，完全不透明时 ravif 会省略 alpha 平面
+pub fn write<W: Write>(image: &Image, w: &mut W, quality: f32, speed: u8) -> Result<()> {
+  let pixels: Vec<RGBA8> = image
+    .as_raw()
+    .chunks_exact(4)
+    .map(|p| RGBA8::new(p[0], p[1], p[2], p[3]))
+    .collect();
+
+  let quality = quality.clamp(1.0, 100.0);
+  let encoded = Encoder::new()
+    .with_quality(quality)
+    .with_alpha_quality(quality)
+    .with_speed(speed.clamp(1, 10))
+    .encode_rgba(Img::new(
+      &pixels[..],
+      image.width() as usize,
+      image.height() as usize,
+    ))
+    .map_err(|err| anyhow!("AVIF encoding failed: {:?}", err))?;
+
+  w.write_all(&encoded.avif_file)?;
+
+  Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::Image;
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+const FILE_HEADER_SIZE: u32 = 14;
+// BITMAPV4HEADER，使用 BI_BITFIELDS 以保留 alpha 通道
+const INFO_HEADER_SIZE: u32 = 108;
+
+pub fn write<W: Write>(image: &Image, w: &mut W) -> Result<()> {
+  let width = i32::try_from(image.width()).map_err(|_| anyhow!("Image too wide for BMP"))?;
+  let height = i32::try_from(image.height()).map_err(|_| anyhow!("Image too tall for BMP"))?;
+  let data_size = u32::try_from(image.as_raw().len())
+    .ok()
+    .filter(|size| {
+      size
+        .checked_add(FILE_HEADER_SIZE + INFO_HEADER_SIZE)
+        .is_some()
+    })
+    .ok_or_else(|| anyhow!("Image too large for BMP"))?;
+  let offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+
+  let mut header = Vec::with_capacity(offset as usize);
+  // BITMAPFILEHEADER
+  header.extend_from_slice(b"BM");
+  header.extend_from_slice(&(offset + data_size).to_le_bytes());
+  header.extend_from_slice(&[0; 4]);
+  header.extend_from_slice(&offset.to_le_bytes());
+  // BITMAPV4HEADER
+  header.extend_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+  header.extend_from_slice(&width.to_le_bytes());
+  header.extend_from_slice(&height.to_le_bytes());
+  header.extend_from_slice(&1u16.to_le_bytes());
+  header.extend_from_slice(&32u16.to_le_bytes());
+  header.extend_from_slice(&3u32.to_le_bytes()); // BI_BITFIELDS
+  header.extend_from_slice(&data_size.to_le_bytes());
+  header.extend_from_slice(&2835u32.to_le_bytes()); // 72 DPI
+  header.extend_from_slice(&2835u32.to_le_bytes());
+  header.extend_from_slice(&[0; 8]);
+  header.extend_from_slice(&0x00ff0000u32.to_le_bytes());
+  header.extend_from_slice(&0x0000ff00u32.to_le_bytes());
+  header.extend_from_slice(&0x000000ffu32.to_le_bytes());
+  header.extend_from_slice(&0xff000000u32.to_le_bytes());
+  header.extend_from_slice(b"BGRs"); // LCS_sRGB
+  header.extend_from_slice(&[0; 48]);
+  w.write_all(&header)?;
+
+  // 行顺序为自下而上
+  let mut row = vec![0u8; image.stride()];
+  for src in image.rows().rev() {
+    for (dst, px) in row.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+      dst.copy_from_slice(&[px[2], px[1], px[0], px[3]]);
+    }
+    w.write_all(&row)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn writes_bottom_up_bgra() {
+    let image = Image::new(
+      2,
+      2,
+      vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+    );
+    let mut data = Vec::new();
+    write(&image, &mut data).unwrap();
+
+    let u32_at = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+    assert_eq!(&data[..2], b"BM");
+    assert_eq!(u32_at(2) as usize, data.len());
+    assert_eq!(u32_at(10), FILE_HEADER_SIZE + INFO_HEADER_SIZE);
+    assert_eq!(u32_at(14), INFO_HEADER_SIZE);
+    assert_eq!((u32_at(18), u32_at(22)), (2, 2));
+    assert_eq!(u32_at(34), 16);
+    assert_eq!(
+      &data[122..],
+      &[11, 10, 9, 12, 15, 14, 13, 16, 3, 2, 1, 4, 7, 6, 5, 8]
+    );
+  }
+}
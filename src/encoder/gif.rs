@@ -0,0 +1,94 @@
+use super::{frame_regions, saturating_delay};
+use crate::{
+  image::{map_to_palette, palette},
+  Image, Quantizer,
+};
+use anyhow::{anyhow, Result};
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use std::{borrow::Cow, io::Write, time::Duration};
+
+pub fn write<W: Write>(
+  frames: &[(Image, Duration)],
+  w: &mut W,
+  plays: u16,
+  quantizer: Quantizer,
+  dither: bool,
+) -> Result<()> {
+  let first = &frames[0].0;
+  let width = u16::try_from(first.width())
+    .map_err(|_| anyhow!("Image width {} exceeds GIF limit", first.width()))?;
+  let height = u16::try_from(first.height())
+    .map_err(|_| anyhow!("Image height {} exceeds GIF limit", first.height()))?;
+
+  // GIF 不支持半透明，先去掉 alpha
+  let opaque: Vec<Cow<Image>> = frames
+    .iter()
+    .map(|(image, _)| {
+      if image.is_opaque() {
+        Cow::Borrowed(image)
+      } else {
+        let mut image = image.clone();
+        image.set_opaque();
+        Cow::Owned(image)
+      }
+    })
+    .collect();
+  let images: Vec<&Image> = opaque.iter().map(|image| image.as_ref()).collect();
+
+  // 所有帧共用一个调色板，最后一个索引留给与上一帧相同的透明像素
+  let palette = palette(&images, 255, quantizer);
+  let transparent = palette.len() as u8;
+  let mut global_palette: Vec<u8> = palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+  global_palette.extend_from_slice(&[0, 0, 0]);
+
+  let indexed: Vec<Vec<u8>> = images
+    .iter()
+    .map(|image| map_to_palette(image, &palette, dither))
+    .collect();
+  let indices: Vec<&[u8]> = indexed.iter().map(Vec::as_slice).collect();
+  let regions = frame_regions(&indices, frames, 1);
+
+  let mut encoder = Encoder::new(w, width, height, &global_palette)?;
+  // NETSCAPE 扩展记录的是重复次数，播放一次时省略
+  match plays {
+    0 => encoder.set_repeat(Repeat::Infinite)?,
+    1 => {}
+    plays => encoder.set_repeat(Repeat::Finite(plays - 1))?,
+  }
+
+  let stride = first.width() as usize;
+  let mut previous: Option<&[u8]> = None;
+  for region in regions {
+    let rect = region.rect;
+    let current = indices[region.index];
+    let mut buffer = Vec::with_capacity(rect.area() as usize);
+    for y in rect.y as usize..rect.bottom() as usize {
+      let row = y * stride + rect.x as usize..y * stride + rect.right() as usize;
+      match previous {
+        Some(previous) => buffer.extend(
+          current[row.clone()]
+            .iter()
+            .zip(&previous[row])
+            .map(|(&index, &old)| if index == old { transparent } else { index }),
+        ),
+        None => buffer.extend_from_slice(&current[row]),
+      }
+    }
+
+    encoder.write_frame(&Frame {
+      delay: saturating_delay((region.delay.as_millis() + 5) / 10),
+      dispose: DisposalMethod::Keep,
+      transparent: previous.map(|_| transparent),
+      left: rect.x as u16,
+      top: rect.y as u16,
+      width: rect.width as u16,
+      height: rect.height as u16,
+      buffer: Cow::Owned(buffer),
+      ..Frame::default()
+    })?;
+    previous = Some(current);
+  }
+  encoder.into_inner()?;
+
+  Ok(())
+}
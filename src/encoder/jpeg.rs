@@ -0,0 +1,19 @@
+use crate::{ColorProfile, Image};
+use anyhow::{anyhow, Result};
+use jpeg_encoder::{ColorType, Encoder};
+use std::io::Write;
+
+pub fn write<W: Write>(image: &Image, w: &mut W, quality: u8) -> Result<()> {
+  let width = u16::try_from(image.width())
+    .map_err(|_| anyhow!("Image width {} exceeds JPEG limit", image.width()))?;
+  let height = u16::try_from(image.height())
+    .map_err(|_| anyhow!("Image height {} exceeds JPEG limit", image.height()))?;
+
+  let mut encoder = Encoder::new(w, quality.clamp(1, 100));
+  if let Some(ColorProfile::Icc(profile)) = image.color_profile() {
+    encoder.add_icc_profile(profile)?;
+  }
+  encoder.encode(image.as_raw(), width, height, ColorType::Rgba)?;
+
+  Ok(())
+}
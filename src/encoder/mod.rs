@@ -0,0 +1,307 @@
+mod apng;
+mod bmp;
+mod gif;
+mod png;
+mod pnm;
+mod qoi;
+mod reduce;
+mod tga;
+
+#[cfg(feature = "jpeg")]
+mod jpeg;
+
+#[cfg(feature = "webp")]
+mod webp;
+
+#[cfg(feature = "avif")]
+mod avif;
+
+use crate::{Image, Quantizer, Rect};
+use anyhow::Result;
+use std::{io::Write, path::Path, time::Duration};
+
+pub use self::png::{PngCompression, PngFilter, PngOptions};
+
+/// Output format used by [`Image::encode`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum ImageFormat {
+  /// 8-bit RGBA PNG, using the image's [`PngOptions`].
+  Png,
+  /// Baseline JPEG, `quality` in `1..=100`. Alpha is discarded.
+  #[cfg(feature = "jpeg")]
+  Jpeg { quality: u8 },
+  /// WebP, `quality` in `0.0..=100.0` (ignored when `lossless`).
+  #[cfg(feature = "webp")]
+  WebP { lossless: bool, quality: f32 },
+  /// AVIF, `quality` in `1.0..=100.0` and `speed` in `1..=10` (slowest to
+  /// fastest). Encoded in pure Rust.
+  #[cfg(feature = "avif")]
+  Avif { quality: f32, speed: u8 },
+  /// 32-bit BMP with an alpha mask.
+  Bmp,
+  /// Binary PPM (P6), alpha is discarded.
+  Ppm,
+  /// PAM (P7) with `RGB_ALPHA` tuples.
+  Pam,
+  /// Uncompressed 32-bit TGA.
+  Tga,
+  /// The Quite OK Image format.
+  Qoi,
+}
+
+impl ImageFormat {
+  /// Guesses the format from a file extension, e.g. `"png"` or `"jpg"`.
+  pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+    match extension.to_ascii_lowercase().as_str() {
+      "png" => Some(ImageFormat::Png),
+      #[cfg(feature = "jpeg")]
+      "jpg" | "jpeg" => Some(ImageFormat::Jpeg { quality: 90 }),
+      #[cfg(feature = "webp")]
+      "webp" => Some(ImageFormat::WebP {
+        lossless: false,
+        quality: 90.0,
+      }),
+      #[cfg(feature = "avif")]
+      "avif" => Some(ImageFormat::Avif {
+        quality: 80.0,
+        speed: 6,
+      }),
+      "bmp" => Some(ImageFormat::Bmp),
+      "ppm" => Some(ImageFormat::Ppm),
+      "pam" => Some(ImageFormat::Pam),
+      "tga" => Some(ImageFormat::Tga),
+      "qoi" => Some(ImageFormat::Qoi),
+      _ => None,
+    }
+  }
+
+  pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+    let extension = path.as_ref().extension()?.to_str()?;
+    ImageFormat::from_extension(extension)
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      ImageFormat::Png => "png",
+      #[cfg(feature = "jpeg")]
+      ImageFormat::Jpeg { .. } => "jpg",
+      #[cfg(feature = "webp")]
+      ImageFormat::WebP { .. } => "webp",
+      #[cfg(feature = "avif")]
+      ImageFormat::Avif { .. } => "avif",
+      ImageFormat::Bmp => "bmp",
+      ImageFormat::Ppm => "ppm",
+      ImageFormat::Pam => "pam",
+      ImageFormat::Tga => "tga",
+      ImageFormat::Qoi => "qoi",
+    }
+  }
+}
+
+pub(crate) fn write<W: Write>(image: &Image, w: &mut W, format: ImageFormat) -> Result<()> {
+  match format {
+    ImageFormat::Png => self::png::write(image, w, image.png_options()),
+    #[cfg(feature = "jpeg")]
+    ImageFormat::Jpeg { quality } => self::jpeg::write(image, w, quality),
+    #[cfg(feature = "webp")]
+    ImageFormat::WebP { lossless, quality } => self::webp::write(image, w, lossless, quality),
+    #[cfg(feature = "avif")]
+    ImageFormat::Avif { quality, speed } => self::avif::write(image, w, quality, speed),
+    ImageFormat::Bmp => bmp::write(image, w),
+    ImageFormat::Ppm => pnm::write_ppm(image, w),
+    ImageFormat::Pam => pnm::write_pam(image, w),
+    ImageFormat::Tga => tga::write(image, w),
+    ImageFormat::Qoi => qoi::write(image, w),
+  }
+}
+
+/// Output format used by [`Animation::encode`](crate::Animation::encode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+  /// Animated 8-bit RGBA PNG, lossless.
+  Apng,
+  /// GIF with one palette of up to 255 colors shared by all frames. Alpha is
+  /// discarded.
+  Gif { quantizer: Quantizer, dither: bool },
+}
+
+impl AnimationFormat {
+  /// Guesses the format from a file extension, `"png"`, `"apng"` or `"gif"`.
+  pub fn from_extension(extension: &str) -> Option<AnimationFormat> {
+    match extension.to_ascii_lowercase().as_str() {
+      "png" | "apng" => Some(AnimationFormat::Apng),
+      "gif" => Some(AnimationFormat::Gif {
+        quantizer: Quantizer::MedianCut,
+        dither: false,
+      }),
+      _ => None,
+    }
+  }
+
+  pub fn from_path<P: AsRef<Path>>(path: P) -> Option<AnimationFormat> {
+    let extension = path.as_ref().extension()?.to_str()?;
+    AnimationFormat::from_extension(extension)
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      AnimationFormat::Apng => "png",
+      AnimationFormat::Gif { .. } => "gif",
+    }
+  }
+}
+
+/// Frame `index` limited to the part that changed since the previous frame.
+struct FrameRegion {
+  index: usize,
+  rect: Rect,
+  delay: Duration,
+}
+
+/// Bounding box of the pixels that differ between two frames.
+fn changed_rect(
+  previous: &[u8],
+  current: &[u8],
+  width: usize,
+  bytes_per_pixel: usize,
+) -> Option<Rect> {
+  let stride = width * bytes_per_pixel;
+  if stride == 0 {
+    return None;
+  }
+  let (mut x1, mut y1, mut x2, mut y2) = (usize::MAX, usize::MAX, 0, 0);
+
+  for (y, (a, b)) in previous
+    .chunks_exact(stride)
+    .zip(current.chunks_exact(stride))
+    .enumerate()
+  {
+    if a == b {
+      continue;
+    }
+    let first = a.iter().zip(b).position(|(a, b)| a != b)?;
+    let last = a.iter().zip(b).rposition(|(a, b)| a != b)?;
+    x1 = x1.min(first / bytes_per_pixel);
+    x2 = x2.max(last / bytes_per_pixel + 1);
+    y1 = y1.min(y);
+    y2 = y + 1;
+  }
+
+  (x1 < x2).then(|| Rect::new(x1 as i32, y1 as i32, (x2 - x1) as u32, (y2 - y1) as u32))
+}
+
+// 第一帧完整写入，之后只写变化区域，与上一帧相同的帧合并到上一帧的时长中
+fn frame_regions(
+  pixels: &[&[u8]],
+  frames: &[(Image, Duration)],
+  bytes_per_pixel: usize,
+) -> Vec<FrameRegion> {
+  let (width, height) = (frames[0].0.width(), frames[0].0.height());
+  let mut regions = vec![FrameRegion {
+    index: 0,
+    rect: Rect::new(0, 0, width, height),
+    delay: frames[0].1,
+  }];
+
+  for (index, (_, delay)) in frames.iter().enumerate().skip(1) {
+    match changed_rect(
+      pixels[index - 1],
+      pixels[index],
+      width as usize,
+      bytes_per_pixel,
+    ) {
+      Some(rect) => regions.push(FrameRegion {
+        index,
+        rect,
+        delay: *delay,
+      }),
+      None => {
+        if let Some(last) = regions.last_mut() {
+          last.delay += *delay;
+        }
+      }
+    }
+  }
+
+  regions
+}
+
+fn saturating_delay(delay: u128) -> u16 {
+  delay.min(u16::MAX as u128) as u16
+}
+
+pub(crate) fn write_animation<W: Write>(
+  frames: &[(Image, Duration)],
+  w: &mut W,
+  format: AnimationFormat,
+  plays: u16,
+) -> Result<()> {
+  match format {
+    AnimationFormat::Apng => apng::write(frames, w, plays),
+    AnimationFormat::Gif { quantizer, dither } => {
+      self::gif::write(frames, w, plays, quantizer, dither)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn changed_rect_bounds() {
+    let previous = vec![0u8; 6 * 4 * 4];
+    let at = |x: usize, y: usize| (y * 6 + x) * 4;
+    assert_eq!(changed_rect(&previous, &previous, 6, 4), None);
+
+    // 只有一个通道变化也算整个像素变化
+    let mut current = previous.clone();
+    current[at(2, 1) + 3] = 1;
+    assert_eq!(
+      changed_rect(&previous, &current, 6, 4),
+      Some(Rect::new(2, 1, 1, 1))
+    );
+
+    current[at(4, 3)] = 1;
+    current[at(1, 2) + 1] = 1;
+    assert_eq!(
+      changed_rect(&previous, &current, 6, 4),
+      Some(Rect::new(1, 1, 4, 3))
+    );
+  }
+
+  #[test]
+  fn changed_rect_indices() {
+    // GIF 的调色板索引每个像素一个字节
+    let previous = vec![0u8; 5 * 3];
+    let mut current = previous.clone();
+    current[2 * 5] = 7;
+    assert_eq!(
+      changed_rect(&previous, &current, 5, 1),
+      Some(Rect::new(0, 2, 1, 1))
+    );
+    assert_eq!(changed_rect(&[], &[], 0, 4), None);
+  }
+
+  #[test]
+  fn unchanged_frames_extend_delay() {
+    let image = |v: u8| Image::new(2, 2, vec![v; 16]);
+    let frames = [
+      (image(0), Duration::from_millis(10)),
+      (image(0), Duration::from_millis(20)),
+      (image(1), Duration::from_millis(30)),
+    ];
+    let pixels: Vec<&[u8]> = frames.iter().map(|(image, _)| image.as_raw()).collect();
+
+    let regions = frame_regions(&pixels, &frames, 4);
+    let summary: Vec<_> = regions.iter().map(|r| (r.index, r.rect, r.delay)).collect();
+    assert_eq!(
+      summary,
+      [
+        (0, Rect::new(0, 0, 2, 2), Duration::from_millis(30)),
+        (2, Rect::new(0, 0, 2, 2), Duration::from_millis(30)),
+      ]
+    );
+  }
+}
@@ -0,0 +1,204 @@
+use super::reduce::{reduce, Reduced};
+use crate::{ColorProfile, Image};
+use anyhow::Result;
+use png::{
+  AdaptiveFilterType, BitDepth, ColorType, Compression, Encoder, FilterType, Info, PixelDimensions,
+  SrgbRenderingIntent, Unit,
+};
+use std::{borrow::Cow, io::Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngCompression {
+  Fast,
+  #[default]
+  Default,
+  Best,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngFilter {
+  None,
+  #[default]
+  Sub,
+  Up,
+  Avg,
+  Paeth,
+}
+
+/// PNG encoder settings, trading file size for encoding time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PngOptions {
+  pub compression: PngCompression,
+  pub filter: PngFilter,
+  /// Pick the best filter per row instead of always using `filter`.
+  pub adaptive_filter: bool,
+  /// Losslessly reduce to RGB, grayscale or indexed color (with lower bit
+  /// depths) when the pixels allow it.
+  pub optimize: bool,
+  /// Try every filter strategy with default and best compression and keep
+  /// the smallest output. Roughly 12 times slower.
+  pub exhaustive: bool,
+}
+
+impl PngOptions {
+  /// Lowest latency preset, for realtime use where size matters less.
+  pub fn fastest() -> Self {
+    PngOptions {
+      compression: PngCompression::Fast,
+      filter: PngFilter::Up,
+      adaptive_filter: false,
+      optimize: false,
+      exhaustive: false,
+    }
+  }
+
+  /// Smallest output, for archiving. Combine with `Image::quantize` for
+  /// lossy savings on top.
+  pub fn smallest() -> Self {
+    PngOptions {
+      compression: PngCompression::Best,
+      filter: PngFilter::Paeth,
+      adaptive_filter: true,
+      optimize: true,
+      exhaustive: true,
+    }
+  }
+}
+
+// 穷举搜索时尝试的过滤策略，最后一项为逐行自适应
+const FILTER_STRATEGIES: [(PngFilter, bool); 6] = [
+  (PngFilter::None, false),
+  (PngFilter::Sub, false),
+  (PngFilter::Up, false),
+  (PngFilter::Avg, false),
+  (PngFilter::Paeth, false),
+  (PngFilter::Sub, true),
+];
+
+pub fn write<W: Write>(image: &Image, w: &mut W, options: &PngOptions) -> Result<()> {
+  let reduced = options.optimize.then(|| reduce(image));
+
+  if !options.exhaustive {
+    return encode(image, reduced.as_ref(), w, options);
+  }
+
+  let mut best: Option<Vec<u8>> = None;
+  for compression in [PngCompression::Default, PngCompression::Best] {
+    for (filter, adaptive_filter) in FILTER_STRATEGIES {
+      let strategy = PngOptions {
+        compression,
+        filter,
+        adaptive_filter,
+        ..*options
+      };
+      let mut buffer = Vec::new();
+      encode(image, reduced.as_ref(), &mut buffer, &strategy)?;
+
+      if best.as_ref().is_none_or(|best| buffer.len() < best.len()) {
+        best = Some(buffer);
+      }
+    }
+  }
+
+  w.write_all(&best.unwrap_or_default())?;
+  Ok(())
+}
+
+fn encode<W: Write>(
+  image: &Image,
+  reduced: Option<&Reduced>,
+  w: &mut W,
+  options: &PngOptions,
+) -> Result<()> {
+  let mut info = Info::with_size(image.width(), image.height());
+  if let Some(ColorProfile::Icc(profile)) = image.color_profile() {
+    info.icc_profile = Some(Cow::Borrowed(profile));
+  }
+
+  let mut encoder = Encoder::with_info(w, info)?;
+  if image.color_profile() == Some(&ColorProfile::Srgb) {
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+  }
+
+  match reduced {
+    Some(reduced) => {
+      encoder.set_color(reduced.color_type);
+      encoder.set_depth(reduced.depth);
+      if reduced.color_type == ColorType::Indexed {
+        encoder.set_palette(reduced.palette.as_slice());
+        if !reduced.trns.is_empty() {
+          encoder.set_trns(reduced.trns.as_slice());
+        }
+      }
+    }
+    None => {
+      encoder.set_color(ColorType::Rgba);
+      encoder.set_depth(BitDepth::Eight);
+    }
+  }
+  encoder.set_compression(match options.compression {
+    PngCompression::Fast => Compression::Fast,
+    PngCompression::Default => Compression::Default,
+    PngCompression::Best => Compression::Best,
+  });
+  encoder.set_filter(match options.filter {
+    PngFilter::None => FilterType::NoFilter,
+    PngFilter::Sub => FilterType::Sub,
+    PngFilter::Up => FilterType::Up,
+    PngFilter::Avg => FilterType::Avg,
+    PngFilter::Paeth => FilterType::Paeth,
+  });
+  encoder.set_adaptive_filter(if options.adaptive_filter {
+    AdaptiveFilterType::Adaptive
+  } else {
+    AdaptiveFilterType::NonAdaptive
+  });
+
+  let metadata = image.metadata();
+  if !metadata.is_empty() {
+    encoder.add_text_chunk(
+      "Software".to_string(),
+      format!("screenshots-rs {}", env!("CARGO_PKG_VERSION")),
+    )?;
+    if let Some(timestamp) = metadata.timestamp {
+      encoder.add_text_chunk("Creation Time".to_string(), timestamp.to_rfc3339())?;
+    }
+    if let Some(backend) = &metadata.backend {
+      encoder.add_text_chunk("Source".to_string(), backend.clone())?;
+    }
+    let text = metadata.to_text();
+    if !text.is_empty() {
+      encoder.add_itxt_chunk("Capture".to_string(), text)?;
+    }
+  }
+
+  // 逻辑像素按 96 DPI 计算，HiDPI 截图在查看器中保持原始物理尺寸
+  if let Some(scale_factor) = metadata.scale_factor.filter(|s| *s > 0.0) {
+    let ppm = (scale_factor as f64 * 96.0 / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(PixelDimensions {
+      xppu: ppm,
+      yppu: ppm,
+      unit: Unit::Meter,
+    }));
+  }
+
+  let mut writer = encoder.write_header()?;
+  let (data, row_bytes) = match reduced {
+    Some(reduced) => (reduced.data.as_slice(), reduced.row_bytes),
+    None => (image.as_raw(), image.stride()),
+  };
+
+  // fdeflate 只在一次性写入时使用，流式写入会退回到 flate2，Fast 模式下前者快得多
+  if options.compression == PngCompression::Fast {
+    writer.write_image_data(data)?;
+  } else {
+    let mut stream = writer.stream_writer()?;
+    for row in data.chunks_exact(row_bytes.max(1)) {
+      stream.write_all(row)?;
+    }
+    stream.finish()?;
+  }
+  writer.finish()?;
+
+  Ok(())
+}
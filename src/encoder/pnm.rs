@@ -0,0 +1,31 @@
+use crate::Image;
+use anyhow::Result;
+use std::io::Write;
+
+/// Binary PPM (P6), alpha is discarded.
+pub fn write_ppm<W: Write>(image: &Image, w: &mut W) -> Result<()> {
+  write!(w, "P6\n{} {}\n255\n", image.width(), image.height())?;
+
+  let mut row = vec![0u8; image.width() as usize * 3];
+  for src in image.rows() {
+    for (dst, px) in row.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+      dst.copy_from_slice(&px[..3]);
+    }
+    w.write_all(&row)?;
+  }
+
+  Ok(())
+}
+
+/// PAM (P7) with `RGB_ALPHA` tuples.
+pub fn write_pam<W: Write>(image: &Image, w: &mut W) -> Result<()> {
+  write!(
+    w,
+    "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+    image.width(),
+    image.height()
+  )?;
+  w.write_all(image.as_raw())?;
+
+  Ok(())
+}
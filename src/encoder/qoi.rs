@@ -0,0 +1,177 @@
+use crate::Image;
+use anyhow::Result;
+use std::io::Write;
+
+// https://qoiformat.org/qoi-specification.pdf
+const QOI_OP_INDEX: u8 = 0x00;
+const QOI_OP_DIFF: u8 = 0x40;
+const QOI_OP_LUMA: u8 = 0x80;
+const QOI_OP_RUN: u8 = 0xc0;
+const QOI_OP_RGB: u8 = 0xfe;
+const QOI_OP_RGBA: u8 = 0xff;
+const QOI_PADDING: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+fn hash(px: [u8; 4]) -> usize {
+  let [r, g, b, a] = px.map(|v| v as usize);
+  (r * 3 + g * 5 + b * 7 + a * 11) % 64
+}
+
+pub fn write<W: Write>(image: &Image, w: &mut W) -> Result<()> {
+  let mut header = [0u8; 14];
+  header[..4].copy_from_slice(b"qoif");
+  header[4..8].copy_from_slice(&image.width().to_be_bytes());
+  header[8..12].copy_from_slice(&image.height().to_be_bytes());
+  header[12] = 4;
+  header[13] = 0;
+  w.write_all(&header)?;
+
+  let mut index = [[0u8; 4]; 64];
+  let mut prev = [0u8, 0, 0, 255];
+  let mut run = 0u8;
+  let mut out = Vec::with_capacity(image.stride() + 8);
+
+  for row in image.rows() {
+    for chunk in row.chunks_exact(4) {
+      let px = [chunk[0], chunk[1], chunk[2], chunk[3]];
+
+      if px == prev {
+        run += 1;
+        if run == 62 {
+          out.push(QOI_OP_RUN | (run - 1));
+          run = 0;
+        }
+        continue;
+      }
+
+      if run > 0 {
+        out.push(QOI_OP_RUN | (run - 1));
+        run = 0;
+      }
+
+      let i = hash(px);
+      if index[i] == px {
+        out.push(QOI_OP_INDEX | i as u8);
+      } else {
+        index[i] = px;
+
+        if px[3] == prev[3] {
+          let vr = px[0].wrapping_sub(prev[0]) as i8;
+          let vg = px[1].wrapping_sub(prev[1]) as i8;
+          let vb = px[2].wrapping_sub(prev[2]) as i8;
+          let vg_r = vr.wrapping_sub(vg);
+          let vg_b = vb.wrapping_sub(vg);
+
+          if (-2..2).contains(&vr) && (-2..2).contains(&vg) && (-2..2).contains(&vb) {
+            out.push(QOI_OP_DIFF | ((vr + 2) as u8) << 4 | ((vg + 2) as u8) << 2 | (vb + 2) as u8);
+          } else if (-8..8).contains(&vg_r) && (-32..32).contains(&vg) && (-8..8).contains(&vg_b) {
+            out.push(QOI_OP_LUMA | (vg + 32) as u8);
+            out.push(((vg_r + 8) as u8) << 4 | (vg_b + 8) as u8);
+          } else {
+            out.extend_from_slice(&[QOI_OP_RGB, px[0], px[1], px[2]]);
+          }
+        } else {
+          out.extend_from_slice(&[QOI_OP_RGBA, px[0], px[1], px[2], px[3]]);
+        }
+      }
+
+      prev = px;
+    }
+
+    w.write_all(&out)?;
+    out.clear();
+  }
+
+  if run > 0 {
+    out.push(QOI_OP_RUN | (run - 1));
+  }
+  out.extend_from_slice(&QOI_PADDING);
+  w.write_all(&out)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode(data: &[u8]) -> (u32, u32, Vec<u8>) {
+    assert_eq!(&data[..4], b"qoif");
+    let width = u32::from_be_bytes(data[4..8].try_into().unwrap());
+    let height = u32::from_be_bytes(data[8..12].try_into().unwrap());
+    assert_eq!(&data[data.len() - 8..], &QOI_PADDING);
+
+    let mut pixels = Vec::new();
+    let mut index = [[0u8; 4]; 64];
+    let mut px = [0u8, 0, 0, 255];
+    let mut i = 14;
+    while i < data.len() - 8 {
+      let op = data[i];
+      i += 1;
+      if op == QOI_OP_RGB {
+        px[..3].copy_from_slice(&data[i..i + 3]);
+        i += 3;
+      } else if op == QOI_OP_RGBA {
+        px.copy_from_slice(&data[i..i + 4]);
+        i += 4;
+      } else {
+        match op & 0xc0 {
+          QOI_OP_INDEX => px = index[op as usize],
+          QOI_OP_DIFF => {
+            px[0] = px[0].wrapping_add((op >> 4 & 3).wrapping_sub(2));
+            px[1] = px[1].wrapping_add((op >> 2 & 3).wrapping_sub(2));
+            px[2] = px[2].wrapping_add((op & 3).wrapping_sub(2));
+          }
+          QOI_OP_LUMA => {
+            let vg = (op & 0x3f).wrapping_sub(32);
+            let next = data[i];
+            i += 1;
+            px[0] = px[0].wrapping_add(vg.wrapping_add(next >> 4).wrapping_sub(8));
+            px[1] = px[1].wrapping_add(vg);
+            px[2] = px[2].wrapping_add(vg.wrapping_add(next & 0x0f).wrapping_sub(8));
+          }
+          _ => {
+            for _ in 0..(op & 0x3f) {
+              pixels.extend_from_slice(&px);
+            }
+          }
+        }
+      }
+      index[hash(px)] = px;
+      pixels.extend_from_slice(&px);
+    }
+
+    (width, height, pixels)
+  }
+
+  #[test]
+  fn round_trips_every_op() {
+    let (width, height) = (37, 30);
+    let mut seed = 1u32;
+    let mut buffer = Vec::new();
+    for i in 0..width * height {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+      let noise = (seed >> 16) as u8;
+      // 最后几行为长游程，其余混合小差值、中等差值、透明度变化和随机颜色
+      let px = match i % 7 {
+        _ if i >= width * 24 => [1, 2, 3, 4],
+        0..=2 => [10, 20, 30, 255],
+        3 => [11, 19, 31, 255],
+        4 => {
+          let vg = noise % 20 + 5;
+          [11 + vg + 1, 19 + vg, 31 + vg - 2, 255]
+        }
+        5 => [noise, noise / 2, 200, noise],
+        _ => [noise, 255 - noise, noise / 3, 255],
+      };
+      buffer.extend_from_slice(&px);
+    }
+    let image = Image::new(width, height, buffer);
+
+    let mut data = Vec::new();
+    write(&image, &mut data).unwrap();
+    let (w, h, pixels) = decode(&data);
+
+    assert_eq!((w, h), (width, height));
+    assert_eq!(pixels, image.as_raw());
+  }
+}
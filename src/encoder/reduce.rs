@@ -0,0 +1,228 @@
+use crate::{ColorProfile, Image};
+use png::{BitDepth, ColorType};
+use std::collections::HashMap;
+
+/// Pixels converted to the smallest lossless PNG color type.
+pub struct Reduced {
+  pub color_type: ColorType,
+  pub depth: BitDepth,
+  /// RGB triples for `ColorType::Indexed`.
+  pub palette: Vec<u8>,
+  /// Alpha of the first palette entries, empty when all are opaque.
+  pub trns: Vec<u8>,
+  /// Packed rows without filter bytes.
+  pub data: Vec<u8>,
+  pub row_bytes: usize,
+}
+
+fn depth_bits(depth: BitDepth) -> usize {
+  match depth {
+    BitDepth::One => 1,
+    BitDepth::Two => 2,
+    BitDepth::Four => 4,
+    _ => 8,
+  }
+}
+
+// 灰度值能被 255/(2^d-1) 整除时才能无损降低位深
+fn gray_depth(values: &[bool; 256]) -> BitDepth {
+  let fits = |step: usize| (0..256).all(|v| !values[v] || v % step == 0);
+  if fits(255) {
+    BitDepth::One
+  } else if fits(85) {
+    BitDepth::Two
+  } else if fits(17) {
+    BitDepth::Four
+  } else {
+    BitDepth::Eight
+  }
+}
+
+fn palette_depth(colors: usize) -> BitDepth {
+  match colors {
+    0..=2 => BitDepth::One,
+    3..=4 => BitDepth::Two,
+    5..=16 => BitDepth::Four,
+    _ => BitDepth::Eight,
+  }
+}
+
+/// Packs one value per pixel into rows of `depth` bits per value.
+fn pack(image: &Image, depth: BitDepth, value: impl Fn(&[u8]) -> u8) -> (Vec<u8>, usize) {
+  let bits = depth_bits(depth);
+  let row_bytes = (image.width() as usize * bits).div_ceil(8);
+  let mut data = vec![0u8; row_bytes * image.height() as usize];
+
+  for (row, out) in image.rows().zip(data.chunks_exact_mut(row_bytes.max(1))) {
+    for (x, p) in row.chunks_exact(4).enumerate() {
+      let bit = x * bits;
+      out[bit / 8] |= value(p) << (8 - bits - bit % 8);
+    }
+  }
+
+  (data, row_bytes)
+}
+
+fn channels(image: &Image, count: usize) -> (Vec<u8>, usize) {
+  let mut data = Vec::with_capacity(image.as_raw().len() / 4 * count);
+  for p in image.as_raw().chunks_exact(4) {
+    match count {
+      2 => data.extend_from_slice(&[p[0], p[3]]),
+      _ => data.extend_from_slice(&p[..count]),
+    }
+  }
+
+  (data, image.width() as usize * count)
+}
+
+pub fn reduce(image: &Image) -> Reduced {
+  let mut opaque = true;
+  let mut gray = true;
+  let mut gray_values = [false; 256];
+  let mut colors: HashMap<[u8; 4], u8> = HashMap::new();
+  let mut indexable = true;
+
+  for p in image.as_raw().chunks_exact(4) {
+    opaque &= p[3] == 255;
+    gray &= p[0] == p[1] && p[1] == p[2];
+    gray_values[p[0] as usize] = true;
+
+    if indexable {
+      let key = [p[0], p[1], p[2], p[3]];
+      if !colors.contains_key(&key) {
+        if colors.len() == 256 {
+          indexable = false;
+        } else {
+          colors.insert(key, 0);
+        }
+      }
+    }
+  }
+
+  // RGB 的 ICC 配置文件不能用于灰度图
+  let gray = gray && !matches!(image.color_profile(), Some(ColorProfile::Icc(_)));
+  let gray_depth = gray_depth(&gray_values);
+  let palette_depth = palette_depth(colors.len());
+
+  if gray && opaque && (!indexable || depth_bits(gray_depth) <= depth_bits(palette_depth)) {
+    let shift = 8 - depth_bits(gray_depth);
+    let (data, row_bytes) = pack(image, gray_depth, |p| p[0] >> shift);
+    return Reduced {
+      color_type: ColorType::Grayscale,
+      depth: gray_depth,
+      palette: Vec::new(),
+      trns: Vec::new(),
+      data,
+      row_bytes,
+    };
+  }
+
+  if indexable {
+    // 半透明的颜色排在前面，tRNS 只需覆盖这些条目
+    let mut entries: Vec<[u8; 4]> = colors.keys().copied().collect();
+    entries.sort_by_key(|c| (c[3] == 255, *c));
+    for (i, color) in entries.iter().enumerate() {
+      colors.insert(*color, i as u8);
+    }
+
+    let palette = entries.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+    let trns = entries
+      .iter()
+      .take_while(|c| c[3] != 255)
+      .map(|c| c[3])
+      .collect();
+    let (data, row_bytes) = pack(image, palette_depth, |p| colors[&[p[0], p[1], p[2], p[3]]]);
+
+    return Reduced {
+      color_type: ColorType::Indexed,
+      depth: palette_depth,
+      palette,
+      trns,
+      data,
+      row_bytes,
+    };
+  }
+
+  let (color_type, count) = match (gray, opaque) {
+    (true, false) => (ColorType::GrayscaleAlpha, 2),
+    (false, true) => (ColorType::Rgb, 3),
+    _ => (ColorType::Rgba, 4),
+  };
+  let (data, row_bytes) = channels(image, count);
+
+  Reduced {
+    color_type,
+    depth: BitDepth::Eight,
+    palette: Vec::new(),
+    trns: Vec::new(),
+    data,
+    row_bytes,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{encoder::png::write, PngOptions};
+
+  // 宽度 5，低位深的行不按字节对齐
+  fn image(pixels: impl Fn(usize) -> [u8; 4]) -> Image {
+    let buffer = (0..5 * 3).flat_map(pixels).collect();
+    Image::new(5, 3, buffer)
+  }
+
+  fn round_trip(image: &Image, color_type: ColorType, depth: BitDepth) {
+    let reduced = reduce(image);
+    assert_eq!((reduced.color_type, reduced.depth), (color_type, depth));
+
+    let options = PngOptions {
+      optimize: true,
+      ..PngOptions::default()
+    };
+    let mut png = Vec::new();
+    write(image, &mut png, &options).unwrap();
+    assert_eq!(Image::from_png(&png).unwrap().as_raw(), image.as_raw());
+  }
+
+  #[test]
+  fn grayscale() {
+    let gray = |v: usize| [v as u8, v as u8, v as u8, 255];
+    round_trip(
+      &image(|i| gray(i % 2 * 255)),
+      ColorType::Grayscale,
+      BitDepth::One,
+    );
+    round_trip(
+      &image(|i| gray(i % 4 * 85)),
+      ColorType::Grayscale,
+      BitDepth::Two,
+    );
+    round_trip(
+      &image(|i| gray(i * 17)),
+      ColorType::Grayscale,
+      BitDepth::Four,
+    );
+  }
+
+  #[test]
+  fn palette_with_alpha() {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 0]];
+    let image = image(|i| colors[i % 3]);
+    round_trip(&image, ColorType::Indexed, BitDepth::Two);
+
+    let reduced = reduce(&image);
+    assert_eq!(reduced.palette.len(), 9);
+    assert_eq!(reduced.trns, [0, 128]);
+  }
+
+  #[test]
+  fn many_colors() {
+    // 300 种颜色，超出调色板
+    let colors = |alpha: fn(usize) -> u8| {
+      let buffer = (0..300).flat_map(|i| [i as u8, (i / 256) as u8, 7, alpha(i)]);
+      Image::new(300, 1, buffer.collect())
+    };
+    round_trip(&colors(|_| 255), ColorType::Rgb, BitDepth::Eight);
+    round_trip(&colors(|i| i as u8), ColorType::Rgba, BitDepth::Eight);
+  }
+}
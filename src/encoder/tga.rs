@@ -0,0 +1,50 @@
+use crate::Image;
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+pub fn write<W: Write>(image: &Image, w: &mut W) -> Result<()> {
+  let width = u16::try_from(image.width()).map_err(|_| anyhow!("Image too wide for TGA"))?;
+  let height = u16::try_from(image.height()).map_err(|_| anyhow!("Image too tall for TGA"))?;
+
+  let mut header = [0u8; 18];
+  // 无压缩真彩色
+  header[2] = 2;
+  header[12..14].copy_from_slice(&width.to_le_bytes());
+  header[14..16].copy_from_slice(&height.to_le_bytes());
+  header[16] = 32;
+  // 8 位 alpha，原点在左上角
+  header[17] = 0x28;
+  w.write_all(&header)?;
+
+  let mut row = vec![0u8; image.stride()];
+  for src in image.rows() {
+    for (dst, px) in row.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+      dst.copy_from_slice(&[px[2], px[1], px[0], px[3]]);
+    }
+    w.write_all(&row)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn writes_top_down_bgra() {
+    let image = Image::new(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut data = Vec::new();
+    write(&image, &mut data).unwrap();
+
+    assert_eq!(data[2], 2);
+    assert_eq!(&data[12..18], &[2, 0, 1, 0, 32, 0x28]);
+    assert_eq!(&data[18..], &[3, 2, 1, 4, 7, 6, 5, 8]);
+  }
+
+  #[test]
+  fn rejects_oversized_images() {
+    let image = Image::new(70_000, 0, Vec::new());
+    assert!(write(&image, &mut Vec::new()).is_err());
+  }
+}
@@ -0,0 +1,16 @@
+use crate::Image;
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use webp::Encoder;
+
+// libwebp 只能编码到内存
+pub fn write<W: Write>(image: &Image, w: &mut W, lossless: bool, quality: f32) -> Result<()> {
+  let encoder = Encoder::from_rgba(image.as_raw(), image.width(), image.height());
+  let memory = encoder
+    .encode_simple(lossless, quality.clamp(0.0, 100.0))
+    .map_err(|err| anyhow!("WebP encoding failed: {:?}", err))?;
+
+  w.write_all(&memory)?;
+
+  Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::{Image, Rect};
+
+/// How the fourth byte of BGRA input is interpreted by
+/// [`Image::from_bgra_with_alpha`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+  /// Padding or garbage, every pixel becomes opaque.
+  #[default]
+  Ignore,
+  /// Straight (unassociated) alpha.
+  Straight,
+  /// Color channels already multiplied by alpha, e.g. X11 ARGB windows
+  /// captured by `capture_window_with`.
+  Premultiplied,
+}
+
+impl Image {
+  /// `true` when every pixel has an alpha of `255`.
+  pub fn is_opaque(&self) -> bool {
+    self.as_raw().chunks_exact(4).all(|p| p[3] == 255)
+  }
+
+  /// Drops the alpha channel, making every pixel opaque.
+  pub fn set_opaque(&mut self) {
+    for p in self.as_raw_mut().chunks_exact_mut(4) {
+      p[3] = 255;
+    }
+  }
+
+  /// Makes every pixel outside `rects` (in pixels) fully transparent.
+  pub fn clear_outside(&mut self, rects: &[Rect]) {
+    let bounds = Rect::new(0, 0, self.width(), self.height());
+    let rects: Vec<Rect> = rects
+      .iter()
+      .filter_map(|rect| rect.intersection(&bounds))
+      .collect();
+
+    let stride = self.stride();
+    for (y, row) in self
+      .as_raw_mut()
+      .chunks_exact_mut(stride.max(1))
+      .enumerate()
+    {
+      // 逐行求出被保留的区间，其余像素清零
+      let mut keep = vec![false; row.len() / 4];
+      for rect in &rects {
+        if (rect.y..rect.bottom()).contains(&(y as i32)) {
+          keep[rect.x as usize..rect.right() as usize].fill(true);
+        }
+      }
+
+      for (p, keep) in row.chunks_exact_mut(4).zip(keep) {
+        if !keep {
+          p.copy_from_slice(&[0, 0, 0, 0]);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,59 @@
+use crate::{annotate, Image};
+
+impl Image {
+  /// Copies `src` onto this image with its top-left corner at `(x, y)`,
+  /// replacing the pixels underneath. Parts outside this image are dropped.
+  pub fn paste(&mut self, src: &Image, x: i32, y: i32) {
+    let x1 = x.max(0);
+    let y1 = y.max(0);
+    let x2 = (x + src.width() as i32).min(self.width() as i32);
+    let y2 = (y + src.height() as i32).min(self.height() as i32);
+
+    if x1 >= x2 || y1 >= y2 {
+      return;
+    }
+
+    let len = (x2 - x1) as usize * 4;
+    let src_x = (x1 - x) as usize * 4;
+    let stride = self.stride();
+    let src_stride = src.stride();
+
+    for row in y1..y2 {
+      let dst_start = row as usize * stride + x1 as usize * 4;
+      let src_start = (row - y) as usize * src_stride + src_x;
+      self.as_raw_mut()[dst_start..dst_start + len]
+        .copy_from_slice(&src.as_raw()[src_start..src_start + len]);
+    }
+  }
+
+  /// Alpha-blends `src` over this image with its top-left corner at `(x, y)`.
+  /// `opacity` in `0.0..=1.0` scales the alpha of `src`.
+  pub fn overlay(&mut self, src: &Image, x: i32, y: i32, opacity: f32) {
+    let x1 = x.max(0);
+    let y1 = y.max(0);
+    let x2 = (x + src.width() as i32).min(self.width() as i32);
+    let y2 = (y + src.height() as i32).min(self.height() as i32);
+
+    if x1 >= x2 || y1 >= y2 {
+      return;
+    }
+
+    let len = (x2 - x1) as usize * 4;
+    let src_x = (x1 - x) as usize * 4;
+    let stride = self.stride();
+    let src_stride = src.stride();
+    let opacity = opacity.clamp(0.0, 1.0);
+
+    for row in y1..y2 {
+      let dst_start = row as usize * stride + x1 as usize * 4;
+      let src_start = (row - y) as usize * src_stride + src_x;
+      let dst = &mut self.as_raw_mut()[dst_start..dst_start + len];
+      for (d, s) in dst
+        .chunks_exact_mut(4)
+        .zip(src.as_raw()[src_start..src_start + len].chunks_exact(4))
+      {
+        annotate::blend(d, [s[0], s[1], s[2], s[3]], opacity);
+      }
+    }
+  }
+}
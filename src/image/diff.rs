@@ -0,0 +1,202 @@
+use crate::{Image, Rect};
+use anyhow::{anyhow, Result};
+
+// 按块合并变化区域，块越大区域越少
+const TILE_SIZE: u32 = 16;
+
+/// Result of [`Image::diff`].
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+  /// Number of pixels whose channels differ by more than the tolerance.
+  pub changed_pixels: u64,
+  /// Bounding boxes of changed regions, nearby changes merged together.
+  pub regions: Vec<Rect>,
+  /// The original image faded out with changed pixels in red, `None` when
+  /// nothing changed.
+  pub diff_image: Option<Image>,
+}
+
+impl DiffResult {
+  pub fn is_identical(&self) -> bool {
+    self.changed_pixels == 0
+  }
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+  while parent[i] != i {
+    parent[i] = parent[parent[i]];
+    i = parent[i];
+  }
+  i
+}
+
+fn merge_overlapping(mut regions: Vec<Rect>) -> Vec<Rect> {
+  let mut merged = true;
+  while merged {
+    merged = false;
+    let mut i = 0;
+    while i < regions.len() {
+      let mut j = i + 1;
+      while j < regions.len() {
+        if regions[i].intersection(&regions[j]).is_some() {
+          let other = regions.swap_remove(j);
+          regions[i] = regions[i].union(&other);
+          merged = true;
+        } else {
+          j += 1;
+        }
+      }
+      i += 1;
+    }
+  }
+  regions
+}
+
+impl Image {
+  /// Compares two images of the same size. A pixel counts as changed when
+  /// any channel differs by more than `tolerance`.
+  pub fn diff(&self, other: &Image, tolerance: u8) -> Result<DiffResult> {
+    if self.width() != other.width() || self.height() != other.height() {
+      return Err(anyhow!(
+        "Image size mismatch: {}x{} and {}x{}",
+        self.width(),
+        self.height(),
+        other.width(),
+        other.height()
+      ));
+    }
+
+    let tiles_x = self.width().div_ceil(TILE_SIZE) as usize;
+    let tiles_y = self.height().div_ceil(TILE_SIZE) as usize;
+    // 每个块内变化像素的包围盒
+    let mut tiles: Vec<Option<Rect>> = vec![None; tiles_x * tiles_y];
+    let mut changed_pixels = 0u64;
+    let mut diff_buffer = Vec::with_capacity(self.as_raw().len());
+
+    for (y, (a, b)) in self.rows().zip(other.rows()).enumerate() {
+      for (x, (pa, pb)) in a.chunks_exact(4).zip(b.chunks_exact(4)).enumerate() {
+        let changed = pa
+          .iter()
+          .zip(pb)
+          .any(|(&ca, &cb)| ca.abs_diff(cb) > tolerance);
+
+        if changed {
+          changed_pixels += 1;
+          let tile = (y / TILE_SIZE as usize) * tiles_x + x / TILE_SIZE as usize;
+          let pixel = Rect::new(x as i32, y as i32, 1, 1);
+          tiles[tile] = Some(tiles[tile].map_or(pixel, |r| r.union(&pixel)));
+          diff_buffer.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+          let luma = (pa[0] as u32 * 299 + pa[1] as u32 * 587 + pa[2] as u32 * 114) / 1000;
+          let faded = (255 - (255 - luma) / 4) as u8;
+          diff_buffer.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+      }
+    }
+
+    if changed_pixels == 0 {
+      return Ok(DiffResult {
+        changed_pixels,
+        regions: Vec::new(),
+        diff_image: None,
+      });
+    }
+
+    // 相邻（含对角）的变化块归为同一区域
+    let mut parent: Vec<usize> = (0..tiles.len()).collect();
+    for ty in 0..tiles_y {
+      for tx in 0..tiles_x {
+        let i = ty * tiles_x + tx;
+        if tiles[i].is_none() {
+          continue;
+        }
+
+        let neighbours = [
+          (tx > 0).then(|| i - 1),
+          (ty > 0).then(|| i - tiles_x),
+          (tx > 0 && ty > 0).then(|| i - tiles_x - 1),
+          (tx + 1 < tiles_x && ty > 0).then(|| i - tiles_x + 1),
+        ];
+
+        for j in neighbours.into_iter().flatten() {
+          if tiles[j].is_some() {
+            let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+            parent[ri] = rj;
+          }
+        }
+      }
+    }
+
+    let mut groups: Vec<Option<Rect>> = vec![None; tiles.len()];
+    for (i, tile) in tiles.iter().enumerate() {
+      if let Some(rect) = *tile {
+        let root = find(&mut parent, i);
+        groups[root] = Some(groups[root].map_or(rect, |r| r.union(&rect)));
+      }
+    }
+
+    let mut regions = merge_overlapping(groups.into_iter().flatten().collect());
+    regions.sort_by_key(|r| (r.y, r.x));
+
+    Ok(DiffResult {
+      changed_pixels,
+      regions,
+      diff_image: Some(self.with_buffer(self.width(), self.height(), diff_buffer)),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gray(width: u32, height: u32) -> Image {
+    Image::new(width, height, vec![128; (width * height * 4) as usize])
+  }
+
+  fn set(image: &mut Image, x: u32, y: u32, px: [u8; 4]) {
+    let i = (y * image.width() + x) as usize * 4;
+    image.as_raw_mut()[i..i + 4].copy_from_slice(&px);
+  }
+
+  #[test]
+  fn identical_images() {
+    let result = gray(20, 20).diff(&gray(20, 20), 0).unwrap();
+    assert!(result.is_identical());
+    assert!(result.regions.is_empty());
+    assert!(result.diff_image.is_none());
+  }
+
+  #[test]
+  fn size_mismatch() {
+    assert!(gray(20, 20).diff(&gray(20, 21), 0).is_err());
+  }
+
+  #[test]
+  fn tolerance() {
+    let mut other = gray(20, 20);
+    set(&mut other, 3, 3, [130, 128, 128, 128]);
+    assert!(gray(20, 20).diff(&other, 2).unwrap().is_identical());
+    assert_eq!(gray(20, 20).diff(&other, 1).unwrap().changed_pixels, 1);
+  }
+
+  #[test]
+  fn regions() {
+    let mut other = gray(100, 100);
+    // 相邻块中的变化合并为一个区域，远处的变化单独成区
+    set(&mut other, 14, 2, [0, 0, 0, 255]);
+    set(&mut other, 17, 5, [0, 0, 0, 255]);
+    set(&mut other, 90, 95, [255, 255, 255, 255]);
+
+    let result = gray(100, 100).diff(&other, 0).unwrap();
+    assert_eq!(result.changed_pixels, 3);
+    assert_eq!(
+      result.regions,
+      vec![Rect::new(14, 2, 4, 4), Rect::new(90, 95, 1, 1)]
+    );
+
+    let diff = result.diff_image.unwrap();
+    let i = (95 * 100 + 90) * 4;
+    assert_eq!(&diff.as_raw()[i..i + 4], &[255, 0, 0, 255]);
+  }
+}
@@ -0,0 +1,143 @@
+use crate::{DisplayInfo, Image, Rect};
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "serde")]
+use anyhow::Result;
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+/// The display an image was captured from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayMetadata {
+  pub id: u32,
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+  pub scale_factor: f32,
+  pub rotation: f32,
+}
+
+impl From<&DisplayInfo> for DisplayMetadata {
+  fn from(display_info: &DisplayInfo) -> Self {
+    DisplayMetadata {
+      id: display_info.id,
+      x: display_info.x,
+      y: display_info.y,
+      width: display_info.width,
+      height: display_info.height,
+      scale_factor: display_info.scale_factor,
+      rotation: display_info.rotation,
+    }
+  }
+}
+
+/// Where an image came from. Filled in by the capture functions and kept by
+/// image operations; written into PNG files as text chunks.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+  pub timestamp: Option<DateTime<Utc>>,
+  /// `None` for captures stitched from several screens.
+  pub display: Option<DisplayMetadata>,
+  /// Captured area in logical pixels, relative to the display, or in global
+  /// desktop coordinates when `display` is `None`.
+  pub area: Option<Rect>,
+  /// Image pixels per logical pixel, written as the PNG `pHYs` chunk.
+  pub scale_factor: Option<f32>,
+  /// Capture backend, e.g. `"x11"`, `"xdg-portal"`, `"gdi"`.
+  pub backend: Option<String>,
+}
+
+impl Metadata {
+  pub fn is_empty(&self) -> bool {
+    *self == Metadata::default()
+  }
+
+  /// Human readable summary, one `key: value` per line.
+  pub(crate) fn to_text(&self) -> String {
+    let mut lines = Vec::new();
+    if let Some(display) = &self.display {
+      lines.push(format!("display_id: {}", display.id));
+      lines.push(format!(
+        "display_geometry: {}x{}+{}+{}",
+        display.width, display.height, display.x, display.y
+      ));
+      lines.push(format!("display_scale_factor: {}", display.scale_factor));
+      lines.push(format!("display_rotation: {}", display.rotation));
+    }
+    if let Some(area) = &self.area {
+      lines.push(format!(
+        "area: {}x{}+{}+{}",
+        area.width, area.height, area.x, area.y
+      ));
+    }
+    if let Some(scale_factor) = self.scale_factor {
+      lines.push(format!("scale_factor: {scale_factor}"));
+    }
+    if let Some(backend) = &self.backend {
+      lines.push(format!("backend: {backend}"));
+    }
+    lines.join("\n")
+  }
+
+  /// Updates `scale_factor` after resampling by `factor` new pixels per old
+  /// pixel. The captured area stays the same.
+  pub(crate) fn rescale(&mut self, factor: f32) {
+    self.scale_factor = self
+      .scale_factor
+      .map(|scale| scale * factor)
+      .filter(|scale| scale.is_finite() && *scale > 0.0);
+  }
+
+  /// Narrows `area` to the part shown by `rect`, in image pixels of an
+  /// unrotated capture. Cleared when there is no scale to convert with.
+  pub(crate) fn crop(&mut self, rect: Rect) {
+    let (Some(area), Some(scale)) = (self.area, self.scale_factor) else {
+      self.area = None;
+      return;
+    };
+
+    let x1 = (rect.x as f32 / scale).floor() as i32;
+    let y1 = (rect.y as f32 / scale).floor() as i32;
+    let x2 = (rect.right() as f32 / scale).ceil() as i32;
+    let y2 = (rect.bottom() as f32 / scale).ceil() as i32;
+    self.area = Rect::new(
+      area.x.saturating_add(x1),
+      area.y.saturating_add(y1),
+      x2.abs_diff(x1),
+      y2.abs_diff(y1),
+    )
+    .intersection(&area);
+  }
+
+  #[cfg(feature = "serde")]
+  pub fn to_json(&self) -> Result<String> {
+    Ok(serde_json::to_string_pretty(self)?)
+  }
+}
+
+impl Image {
+  pub fn metadata(&self) -> &Metadata {
+    &self.metadata
+  }
+
+  pub fn metadata_mut(&mut self) -> &mut Metadata {
+    &mut self.metadata
+  }
+
+  pub fn set_metadata(&mut self, metadata: Metadata) {
+    self.metadata = metadata;
+  }
+
+  /// Writes the metadata as JSON next to an image saved at `path`, e.g.
+  /// `shot.png` gets `shot.json`.
+  #[cfg(feature = "serde")]
+  pub fn save_sidecar<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let path = path.as_ref().with_extension("json");
+    fs::write(path, self.metadata.to_json()?)?;
+
+    Ok(())
+  }
+}
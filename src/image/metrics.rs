@@ -0,0 +1,183 @@
+use crate::{Filter, Image};
+use anyhow::{anyhow, Result};
+use std::{f64::consts::PI, fmt};
+
+const SSIM_WINDOW: usize = 8;
+const SSIM_STEP: usize = 4;
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// A 64-bit perceptual fingerprint, see [`Image::dhash`] and [`Image::phash`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+  /// Hamming distance, `0` for identical fingerprints and up to `64`.
+  pub fn distance(&self, other: &ImageHash) -> u32 {
+    (self.0 ^ other.0).count_ones()
+  }
+}
+
+impl fmt::Display for ImageHash {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:016x}", self.0)
+  }
+}
+
+fn check_size(a: &Image, b: &Image) -> Result<()> {
+  if a.width() != b.width() || a.height() != b.height() {
+    return Err(anyhow!(
+      "Image size mismatch: {}x{} and {}x{}",
+      a.width(),
+      a.height(),
+      b.width(),
+      b.height()
+    ));
+  }
+  Ok(())
+}
+
+fn luma(image: &Image) -> Vec<f64> {
+  image
+    .as_raw()
+    .chunks_exact(4)
+    .map(|p| 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64)
+    .collect()
+}
+
+fn window_ssim(a: &[f64], b: &[f64], width: usize, x: usize, y: usize, w: usize, h: usize) -> f64 {
+  let n = (w * h) as f64;
+  let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+  for row in y..y + h {
+    for i in row * width + x..row * width + x + w {
+      sum_a += a[i];
+      sum_b += b[i];
+      sum_aa += a[i] * a[i];
+      sum_bb += b[i] * b[i];
+      sum_ab += a[i] * b[i];
+    }
+  }
+
+  let mean_a = sum_a / n;
+  let mean_b = sum_b / n;
+  let var_a = sum_aa / n - mean_a * mean_a;
+  let var_b = sum_bb / n - mean_b * mean_b;
+  let cov = sum_ab / n - mean_a * mean_b;
+
+  ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * cov + SSIM_C2))
+    / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2))
+}
+
+impl Image {
+  /// Mean structural similarity of the luma channel, `1.0` for identical
+  /// images. Computed over 8x8 windows with a step of 4 pixels.
+  pub fn ssim(&self, other: &Image) -> Result<f64> {
+    check_size(self, other)?;
+
+    let (width, height) = (self.width() as usize, self.height() as usize);
+    if width == 0 || height == 0 {
+      return Ok(1.0);
+    }
+
+    let a = luma(self);
+    let b = luma(other);
+    let w = SSIM_WINDOW.min(width);
+    let h = SSIM_WINDOW.min(height);
+
+    let mut total = 0.0;
+    let mut count = 0usize;
+    for y in (0..=height - h).step_by(SSIM_STEP) {
+      for x in (0..=width - w).step_by(SSIM_STEP) {
+        total += window_ssim(&a, &b, width, x, y, w, h);
+        count += 1;
+      }
+    }
+
+    Ok(total / count as f64)
+  }
+
+  /// Peak signal-to-noise ratio of the RGB channels in dB, infinite for
+  /// identical images.
+  pub fn psnr(&self, other: &Image) -> Result<f64> {
+    check_size(self, other)?;
+
+    let mut sum = 0u64;
+    let mut count = 0u64;
+    for (pa, pb) in self
+      .as_raw()
+      .chunks_exact(4)
+      .zip(other.as_raw().chunks_exact(4))
+    {
+      for c in 0..3 {
+        let d = pa[c].abs_diff(pb[c]) as u64;
+        sum += d * d;
+      }
+      count += 3;
+    }
+
+    if sum == 0 {
+      return Ok(f64::INFINITY);
+    }
+
+    let mse = sum as f64 / count as f64;
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+  }
+
+  /// Difference hash: compares neighbouring pixels of a 9x8 grayscale
+  /// thumbnail. Cheap and robust against scaling and small color shifts.
+  pub fn dhash(&self) -> ImageHash {
+    let small = luma(&self.resize(9, 8, Filter::Bilinear));
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+      for x in 0..8 {
+        hash <<= 1;
+        if small[y * 9 + x] < small[y * 9 + x + 1] {
+          hash |= 1;
+        }
+      }
+    }
+
+    ImageHash(hash)
+  }
+
+  /// Perceptual hash: compares the low frequencies of a 32x32 grayscale DCT
+  /// against their median.
+  pub fn phash(&self) -> ImageHash {
+    const N: usize = 32;
+    let small = luma(&self.resize(N as u32, N as u32, Filter::Bilinear));
+
+    let cos: Vec<f64> = (0..8)
+      .flat_map(|u| {
+        (0..N).map(move |x| ((2 * x + 1) as f64 * u as f64 * PI / (2 * N) as f64).cos())
+      })
+      .collect();
+
+    // 只计算左上角 8x8 的低频系数
+    let mut rows = [[0f64; 8]; N];
+    for (y, row) in rows.iter_mut().enumerate() {
+      for (u, value) in row.iter_mut().enumerate() {
+        *value = (0..N).map(|x| small[y * N + x] * cos[u * N + x]).sum();
+      }
+    }
+
+    let mut coefficients = [0f64; 64];
+    for v in 0..8 {
+      for u in 0..8 {
+        coefficients[v * 8 + u] = (0..N).map(|y| rows[y][u] * cos[v * N + y]).sum();
+      }
+    }
+
+    // 跳过直流分量求中位数
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    let hash = coefficients
+      .iter()
+      .fold(0u64, |hash, &c| (hash << 1) | (c > median) as u64);
+
+    ImageHash(hash)
+  }
+}
@@ -0,0 +1,318 @@
+mod alpha;
+mod compose;
+mod diff;
+mod metadata;
+mod metrics;
+mod profile;
+mod quantize;
+mod redact;
+mod resize;
+mod transform;
+mod view;
+
+pub use alpha::AlphaMode;
+pub use diff::DiffResult;
+pub use metadata::{DisplayMetadata, Metadata};
+pub use metrics::ImageHash;
+pub use profile::ColorProfile;
+pub use quantize::Quantizer;
+pub(crate) use quantize::{map_to_palette, palette};
+pub use redact::RedactStyle;
+pub use resize::Filter;
+pub use view::SubImage;
+
+use crate::{
+  encoder::{self, ImageFormat, PngOptions},
+  Rect,
+};
+use anyhow::{anyhow, Result};
+use png::{ColorType, Decoder, Transformations};
+use std::{
+  fmt,
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+  slice::ChunksExact,
+};
+
+/// A captured image, stored as raw 8-bit RGBA pixels without row padding.
+#[derive(Clone)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  buffer: Vec<u8>,
+  png_options: PngOptions,
+  metadata: Metadata,
+  color_profile: Option<ColorProfile>,
+}
+
+impl Image {
+  /// Wraps RGBA pixels, the caller guarantees `buffer` holds `width * height * 4` bytes.
+  pub(crate) fn new(width: u32, height: u32, buffer: Vec<u8>) -> Self {
+    Image {
+      width,
+      height,
+      buffer,
+      png_options: PngOptions::default(),
+      metadata: Metadata::default(),
+      color_profile: None,
+    }
+  }
+
+  /// Wraps raw 8-bit RGBA pixels without row padding. Fails when `buffer`
+  /// is not exactly `width * height * 4` bytes long.
+  pub fn from_raw(width: u32, height: u32, buffer: Vec<u8>) -> Result<Self> {
+    let expected = width as u64 * height as u64 * 4;
+    if buffer.len() as u64 != expected {
+      return Err(anyhow!(
+        "RGBA buffer size mismatch: {} bytes for {}x{}, expected {}",
+        buffer.len(),
+        width,
+        height,
+        expected
+      ));
+    }
+
+    Ok(Image::new(width, height, buffer))
+  }
+
+  /// Converts BGRA pixels to an opaque image, the alpha byte is ignored.
+  pub fn from_bgra(bgra: Vec<u8>, width: u32, height: u32, bytes_per_row: usize) -> Result<Self> {
+    Image::from_bgra_with_alpha(bgra, width, height, bytes_per_row, AlphaMode::Ignore)
+  }
+
+  /// Converts BGRA pixels, interpreting the alpha byte according to `alpha`.
+  pub fn from_bgra_with_alpha(
+    bgra: Vec<u8>,
+    width: u32,
+    height: u32,
+    bytes_per_row: usize,
+    alpha: AlphaMode,
+  ) -> Result<Self> {
+    Image::from_bgra_slice(&bgra, width, height, bytes_per_row, alpha)
+  }
+
+  /// Same as `from_bgra_with_alpha`, for backends that keep their buffer
+  /// between captures.
+  pub(crate) fn from_bgra_slice(
+    bgra: &[u8],
+    width: u32,
+    height: u32,
+    bytes_per_row: usize,
+    alpha: AlphaMode,
+  ) -> Result<Self> {
+    let u_width = width as usize;
+    let u_height = height as usize;
+
+    if u_height > 0 && bgra.len() < bytes_per_row * (u_height - 1) + u_width * 4 {
+      return Err(anyhow!(
+        "BGRA buffer too small: {} bytes for {}x{} with {} bytes per row",
+        bgra.len(),
+        width,
+        height,
+        bytes_per_row
+      ));
+    }
+
+    let mut bytes = vec![0u8; u_width * u_height * 4];
+
+    // 数据对齐，有时传入 bgra 每一行像素点多余宽度值
+    // 例如在 mac 上，截图尺寸为10*10时，返回的数据长度大于400
+    // https://github.com/nashaofu/screenshots-rs/issues/29
+    // https://github.com/nashaofu/screenshots-rs/issues/38
+    // BGRA 转换为 RGBA
+    for r in 0..u_height {
+      for c in 0..u_width {
+        let index = (r * u_width + c) * 4;
+        let i = r * bytes_per_row + c * 4;
+        let mut pixel = [bgra[i + 2], bgra[i + 1], bgra[i], bgra[i + 3]];
+
+        match alpha {
+          AlphaMode::Ignore => pixel[3] = 255,
+          AlphaMode::Straight => {}
+          // 预乘 alpha 还原为直通 alpha
+          AlphaMode::Premultiplied => {
+            let a = pixel[3] as u32;
+            for c in &mut pixel[..3] {
+              *c = ((*c as u32 * 255 + a / 2).checked_div(a).unwrap_or(0)).min(255) as u8;
+            }
+          }
+        }
+
+        bytes[index..index + 4].copy_from_slice(&pixel);
+      }
+    }
+
+    Ok(Image::new(width, height, bytes))
+  }
+
+  /// Decodes a PNG file into raw RGBA pixels.
+  pub fn from_png(buffer: &[u8]) -> Result<Self> {
+    let mut decoder = Decoder::new(buffer);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let bytes = &buf[..info.buffer_size()];
+
+    let rgba = match info.color_type {
+      ColorType::Rgba => bytes.to_vec(),
+      ColorType::Rgb => bytes
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect(),
+      ColorType::GrayscaleAlpha => bytes
+        .chunks_exact(2)
+        .flat_map(|p| [p[0], p[0], p[0], p[1]])
+        .collect(),
+      ColorType::Grayscale => bytes.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+      ColorType::Indexed => return Err(anyhow!("Unexpanded indexed PNG")),
+    };
+
+    let mut image = Image::new(info.width, info.height, rgba);
+    let png_info = reader.info();
+    if let Some(icc_profile) = &png_info.icc_profile {
+      image.set_color_profile(Some(ColorProfile::Icc(icc_profile.to_vec())));
+    } else if png_info.srgb.is_some() {
+      image.set_color_profile(Some(ColorProfile::Srgb));
+    }
+
+    Ok(image)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// Number of bytes between the start of two consecutive rows.
+  pub fn stride(&self) -> usize {
+    self.width as usize * 4
+  }
+
+  /// Returns the RGBA value at `(x, y)`, or `None` when out of bounds.
+  pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+
+    let i = y as usize * self.stride() + x as usize * 4;
+    let p = self.buffer.get(i..i + 4)?;
+    Some([p[0], p[1], p[2], p[3]])
+  }
+
+  /// Returns the RGBA bytes of row `y`.
+  pub fn row(&self, y: u32) -> Option<&[u8]> {
+    if y >= self.height {
+      return None;
+    }
+
+    let start = y as usize * self.stride();
+    self.buffer.get(start..start + self.stride())
+  }
+
+  pub fn rows(&self) -> ChunksExact<'_, u8> {
+    self.buffer.chunks_exact(self.stride().max(1))
+  }
+
+  /// Raw RGBA pixels, `stride()` bytes per row.
+  pub fn as_raw(&self) -> &[u8] {
+    &self.buffer
+  }
+
+  pub fn as_raw_mut(&mut self) -> &mut [u8] {
+    &mut self.buffer
+  }
+
+  pub fn into_raw(self) -> Vec<u8> {
+    self.buffer
+  }
+
+  /// PNG settings used by `to_png()` and `ImageFormat::Png`.
+  pub fn png_options(&self) -> &PngOptions {
+    &self.png_options
+  }
+
+  pub fn set_png_options(&mut self, png_options: PngOptions) {
+    self.png_options = png_options;
+  }
+
+  /// A zero-copy view of `rect`, clamped to the image bounds.
+  pub fn view(&self, rect: Rect) -> Result<SubImage<'_>> {
+    SubImage::new(self, rect)
+  }
+
+  /// Copies `rect`, clamped to the image bounds, into a new image.
+  pub fn crop(&self, rect: Rect) -> Result<Image> {
+    Ok(self.view(rect)?.to_image())
+  }
+
+  /// A new image with the same encoding settings and metadata as this one.
+  pub(crate) fn with_buffer(&self, width: u32, height: u32, buffer: Vec<u8>) -> Image {
+    Image {
+      width,
+      height,
+      buffer,
+      png_options: self.png_options,
+      metadata: self.metadata.clone(),
+      color_profile: self.color_profile.clone(),
+    }
+  }
+
+  /// Encodes the image in the given format.
+  pub fn encode(&self, format: ImageFormat) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    self.write_to(&mut buffer, format)?;
+    Ok(buffer)
+  }
+
+  /// Encodes the image row by row into `w`.
+  pub fn write_to<W: Write>(&self, w: &mut W, format: ImageFormat) -> Result<()> {
+    encoder::write(self, w, format)
+  }
+
+  /// Saves the image, inferring the format from the file extension.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path)
+      .ok_or_else(|| anyhow!("Unsupported image format: {}", path.display()))?;
+
+    self.save_with_format(path, format)
+  }
+
+  pub fn save_with_format<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    self.write_to(&mut writer, format)?;
+    writer.flush()?;
+
+    Ok(())
+  }
+
+  /// Encodes the image as an RGBA PNG.
+  pub fn to_png(&self) -> Result<Vec<u8>> {
+    self.encode(ImageFormat::Png)
+  }
+}
+
+// 只比较像素，编码参数、元数据和色彩配置不影响相等性
+impl PartialEq for Image {
+  fn eq(&self, other: &Self) -> bool {
+    self.width == other.width && self.height == other.height && self.buffer == other.buffer
+  }
+}
+
+impl Eq for Image {}
+
+impl fmt::Debug for Image {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Image")
+      .field("width", &self.width)
+      .field("height", &self.height)
+      .field("buffer", &format_args!("[u8; {}]", self.buffer.len()))
+      .finish()
+  }
+}
@@ -0,0 +1,23 @@
+use crate::Image;
+
+/// Color space of an image's pixels, embedded by the PNG and JPEG encoders
+/// so viewers do not have to guess.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorProfile {
+  /// Tagged with the PNG `sRGB` chunk.
+  Srgb,
+  /// A raw ICC profile, e.g. the display's profile.
+  Icc(Vec<u8>),
+}
+
+impl Image {
+  /// `None` for untagged images. Captures are tagged with the display's ICC
+  /// profile when the backend can read it, `ColorProfile::Srgb` otherwise.
+  pub fn color_profile(&self) -> Option<&ColorProfile> {
+    self.color_profile.as_ref()
+  }
+
+  pub fn set_color_profile(&mut self, color_profile: Option<ColorProfile>) {
+    self.color_profile = color_profile;
+  }
+}
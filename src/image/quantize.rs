@@ -0,0 +1,266 @@
+use crate::Image;
+use std::{
+  array,
+  collections::{HashMap, HashSet},
+  mem,
+};
+
+// 颜色过多时先丢弃低位合并直方图，均值仍按原始颜色累加
+const MAX_HISTOGRAM: usize = 1 << 16;
+const KMEANS_ITERATIONS: usize = 8;
+
+/// Palette generation algorithm used by [`Image::quantize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantizer {
+  /// Recursively splits the color box with the widest channel at its median.
+  #[default]
+  MedianCut,
+  /// Median cut refined with k-means. Slower, lower error.
+  KMeans,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+  sum: [f64; 4],
+  count: u64,
+}
+
+impl Bucket {
+  fn mean(&self) -> [f64; 4] {
+    self.sum.map(|v| v / self.count as f64)
+  }
+}
+
+fn histogram(images: &[&Image]) -> Vec<Bucket> {
+  let mut shift = 0;
+  'retry: loop {
+    let mask = 0xffu8 << shift;
+    let mut buckets: HashMap<[u8; 4], Bucket> = HashMap::new();
+
+    for p in images
+      .iter()
+      .flat_map(|image| image.as_raw().chunks_exact(4))
+    {
+      let key = [p[0] & mask, p[1] & mask, p[2] & mask, p[3] & mask];
+      let bucket = buckets.entry(key).or_insert(Bucket {
+        sum: [0.0; 4],
+        count: 0,
+      });
+      (0..4).for_each(|c| bucket.sum[c] += p[c] as f64);
+      bucket.count += 1;
+
+      if buckets.len() > MAX_HISTOGRAM {
+        shift += 1;
+        continue 'retry;
+      }
+    }
+
+    return buckets.into_values().collect();
+  }
+}
+
+/// Distinct colors of `images`, collection stops at `limit`.
+fn distinct_colors(images: &[&Image], limit: usize) -> HashSet<[u8; 4]> {
+  let mut seen = HashSet::new();
+  for p in images
+    .iter()
+    .flat_map(|image| image.as_raw().chunks_exact(4))
+  {
+    seen.insert([p[0], p[1], p[2], p[3]]);
+    if seen.len() >= limit {
+      break;
+    }
+  }
+  seen
+}
+
+fn distance(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+  (0..4).map(|c| (a[c] - b[c]) * (a[c] - b[c])).sum()
+}
+
+fn median_cut(mut buckets: Vec<Bucket>, colors: usize) -> Vec<[f64; 4]> {
+  // 每个盒子是 buckets 中的一段区间
+  let mut boxes = vec![(0, buckets.len())];
+
+  while boxes.len() < colors {
+    let widest = boxes
+      .iter()
+      .enumerate()
+      .filter(|(_, (start, end))| end - start > 1)
+      .map(|(i, &(start, end))| {
+        let means: Vec<[f64; 4]> = buckets[start..end].iter().map(Bucket::mean).collect();
+        let (channel, range) = (0..4)
+          .map(|c| {
+            let (min, max) = means.iter().fold((f64::MAX, f64::MIN), |(min, max), m| {
+              (min.min(m[c]), max.max(m[c]))
+            });
+            (c, max - min)
+          })
+          .max_by(|a, b| a.1.total_cmp(&b.1))
+          .unwrap_or((0, 0.0));
+        (i, channel, range)
+      })
+      .max_by(|a, b| a.2.total_cmp(&b.2));
+
+    let Some((i, channel, range)) = widest else {
+      break;
+    };
+    if range <= 0.0 {
+      break;
+    }
+
+    let (start, end) = boxes[i];
+    let slice = &mut buckets[start..end];
+    slice.sort_by(|a, b| a.mean()[channel].total_cmp(&b.mean()[channel]));
+
+    // 按像素数取加权中位数
+    let total: u64 = slice.iter().map(|b| b.count).sum();
+    let mut acc = 0;
+    let mut split = 1;
+    for (j, bucket) in slice.iter().enumerate() {
+      acc += bucket.count;
+      if acc * 2 >= total {
+        split = (j + 1).clamp(1, slice.len() - 1);
+        break;
+      }
+    }
+
+    boxes[i] = (start, start + split);
+    boxes.push((start + split, end));
+  }
+
+  boxes
+    .iter()
+    .map(|&(start, end)| {
+      let (sum, count) = buckets[start..end]
+        .iter()
+        .fold(([0.0; 4], 0), |(mut sum, count), b| {
+          (0..4).for_each(|c| sum[c] += b.sum[c]);
+          (sum, count + b.count)
+        });
+      sum.map(|v| v / count as f64)
+    })
+    .collect()
+}
+
+fn nearest(palette: &[[f64; 4]], color: &[f64; 4]) -> usize {
+  palette
+    .iter()
+    .enumerate()
+    .map(|(i, p)| (i, distance(p, color)))
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map_or(0, |(i, _)| i)
+}
+
+fn kmeans(buckets: &[Bucket], mut palette: Vec<[f64; 4]>) -> Vec<[f64; 4]> {
+  for _ in 0..KMEANS_ITERATIONS {
+    let mut sums = vec![([0.0; 4], 0u64); palette.len()];
+    for bucket in buckets {
+      let (sum, count) = &mut sums[nearest(&palette, &bucket.mean())];
+      (0..4).for_each(|c| sum[c] += bucket.sum[c]);
+      *count += bucket.count;
+    }
+
+    let mut moved = false;
+    for (color, (sum, count)) in palette.iter_mut().zip(sums) {
+      // 空簇保留原位置
+      if count > 0 {
+        let mean = sum.map(|v| v / count as f64);
+        moved |= distance(color, &mean) > 0.25;
+        *color = mean;
+      }
+    }
+
+    if !moved {
+      break;
+    }
+  }
+
+  palette
+}
+
+/// Palette of at most `colors` entries shared by all `images`, exact when
+/// they have no more colors than that.
+pub(crate) fn palette(images: &[&Image], colors: usize, quantizer: Quantizer) -> Vec<[u8; 4]> {
+  let distinct = distinct_colors(images, colors + 1);
+  if distinct.len() <= colors {
+    let mut palette: Vec<[u8; 4]> = distinct.into_iter().collect();
+    palette.sort_unstable();
+    return palette;
+  }
+
+  let buckets = histogram(images);
+  let mut palette = median_cut(buckets.clone(), colors);
+  if quantizer == Quantizer::KMeans {
+    palette = kmeans(&buckets, palette);
+  }
+  palette
+    .iter()
+    .map(|c| c.map(|v| v.round().clamp(0.0, 255.0) as u8))
+    .collect()
+}
+
+/// Index of the nearest `palette` entry (at most 256) for every pixel.
+pub(crate) fn map_to_palette(image: &Image, palette: &[[u8; 4]], dither: bool) -> Vec<u8> {
+  let palette: Vec<[f64; 4]> = palette.iter().map(|c| c.map(|v| v as f64)).collect();
+  let width = image.width() as usize;
+  let mut indices = Vec::with_capacity(image.as_raw().len() / 4);
+  let mut cache: HashMap<[u8; 4], usize> = HashMap::new();
+
+  if dither {
+    // Floyd-Steinberg 误差扩散
+    let mut current = vec![[0f64; 4]; width + 2];
+    let mut next = vec![[0f64; 4]; width + 2];
+    for row in image.rows() {
+      for (x, p) in row.chunks_exact(4).enumerate() {
+        let color: [f64; 4] =
+          array::from_fn(|c| (p[c] as f64 + current[x + 1][c]).clamp(0.0, 255.0));
+        let key = color.map(|v| v.round() as u8);
+        let i = *cache
+          .entry(key)
+          .or_insert_with(|| nearest(&palette, &key.map(|v| v as f64)));
+        indices.push(i as u8);
+
+        for c in 0..4 {
+          let error = color[c] - palette[i][c];
+          current[x + 2][c] += error * 7.0 / 16.0;
+          next[x][c] += error * 3.0 / 16.0;
+          next[x + 1][c] += error * 5.0 / 16.0;
+          next[x + 2][c] += error / 16.0;
+        }
+      }
+      mem::swap(&mut current, &mut next);
+      next.iter_mut().for_each(|e| *e = [0.0; 4]);
+    }
+  } else {
+    for p in image.as_raw().chunks_exact(4) {
+      let key = [p[0], p[1], p[2], p[3]];
+      let i = *cache
+        .entry(key)
+        .or_insert_with(|| nearest(&palette, &key.map(|v| v as f64)));
+      indices.push(i as u8);
+    }
+  }
+
+  indices
+}
+
+impl Image {
+  /// Reduces the image to at most `colors` (`2..=256`) colors. Images that
+  /// already fit are returned unchanged. Set `PngOptions::optimize` to write
+  /// the result as an indexed PNG.
+  pub fn quantize(&self, colors: u32, quantizer: Quantizer, dither: bool) -> Image {
+    let colors = colors.clamp(2, 256) as usize;
+    if distinct_colors(&[self], colors + 1).len() <= colors {
+      return self.clone();
+    }
+
+    let palette = palette(&[self], colors, quantizer);
+    let buffer = map_to_palette(self, &palette, dither)
+      .into_iter()
+      .flat_map(|i| palette[i as usize])
+      .collect();
+
+    self.with_buffer(self.width(), self.height(), buffer)
+  }
+}
@@ -0,0 +1,120 @@
+use crate::{Image, Rect};
+
+/// How a redacted region is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactStyle {
+  /// Opaque black fill. The only style that is not reversible in theory.
+  Fill,
+  /// Averages blocks of `n` x `n` pixels.
+  Pixelate(u32),
+  /// Box blur with the given radius, applied three times.
+  Blur(u32),
+}
+
+fn box_blur_line(line: &mut [[u32; 4]], radius: usize, scratch: &mut Vec<[u32; 4]>) {
+  let len = line.len();
+  scratch.clear();
+  scratch.extend_from_slice(line);
+
+  let window = radius as u64 * 2 + 1;
+  let at = |i: i64| scratch[i.clamp(0, len as i64 - 1) as usize];
+  // 窗口超出边界的部分重复边缘像素，按次数累加，避免大半径时逐个遍历
+  let mut sum = [0u64; 4];
+  let mut add = |p: [u32; 4], n: u64| (0..4).for_each(|c| sum[c] += p[c] as u64 * n);
+  add(scratch[0], radius as u64 + 1);
+  for &p in &scratch[1..=radius.min(len - 1)] {
+    add(p, 1);
+  }
+  if radius >= len {
+    add(scratch[len - 1], (radius - len + 1) as u64);
+  }
+
+  for (i, out) in line.iter_mut().enumerate() {
+    *out = sum.map(|v| ((v + window / 2) / window) as u32);
+    let add = at(i as i64 + radius as i64 + 1);
+    let sub = at(i as i64 - radius as i64);
+    (0..4).for_each(|c| sum[c] = sum[c] + add[c] as u64 - sub[c] as u64);
+  }
+}
+
+impl Image {
+  /// Irreversibly hides `rect` (in pixels, clamped to the image).
+  pub fn redact(&mut self, rect: Rect, style: RedactStyle) {
+    let bounds = Rect::new(0, 0, self.width(), self.height());
+    let Some(rect) = rect.intersection(&bounds) else {
+      return;
+    };
+
+    let stride = self.stride();
+    let (x0, y0) = (rect.x as usize, rect.y as usize);
+    let (w, h) = (rect.width as usize, rect.height as usize);
+    let buffer = self.as_raw_mut();
+    let index = |x: usize, y: usize| (y0 + y) * stride + (x0 + x) * 4;
+
+    match style {
+      RedactStyle::Fill => {
+        for y in 0..h {
+          for p in buffer[index(0, y)..index(w, y)].chunks_exact_mut(4) {
+            p.copy_from_slice(&[0, 0, 0, 255]);
+          }
+        }
+      }
+      RedactStyle::Pixelate(n) => {
+        let n = n.max(1) as usize;
+        for by in (0..h).step_by(n) {
+          for bx in (0..w).step_by(n) {
+            let (bw, bh) = (n.min(w - bx), n.min(h - by));
+            let mut sum = [0u64; 4];
+            for y in by..by + bh {
+              for p in buffer[index(bx, y)..index(bx + bw, y)].chunks_exact(4) {
+                (0..4).for_each(|c| sum[c] += p[c] as u64);
+              }
+            }
+
+            let count = (bw * bh) as u64;
+            let average = sum.map(|v| ((v + count / 2) / count) as u8);
+            for y in by..by + bh {
+              for p in buffer[index(bx, y)..index(bx + bw, y)].chunks_exact_mut(4) {
+                p.copy_from_slice(&average);
+              }
+            }
+          }
+        }
+      }
+      RedactStyle::Blur(radius) => {
+        let radius = radius.max(1) as usize;
+        let mut pixels: Vec<[u32; 4]> = (0..h)
+          .flat_map(|y| {
+            buffer[index(0, y)..index(w, y)]
+              .chunks_exact(4)
+              .map(|p| [p[0] as u32, p[1] as u32, p[2] as u32, p[3] as u32])
+              .collect::<Vec<_>>()
+          })
+          .collect();
+
+        let mut scratch = Vec::with_capacity(w.max(h));
+        let mut column = vec![[0u32; 4]; h];
+        // 三次盒式模糊近似高斯模糊，只使用区域内的像素
+        for _ in 0..3 {
+          for row in pixels.chunks_exact_mut(w) {
+            box_blur_line(row, radius, &mut scratch);
+          }
+          for x in 0..w {
+            (0..h).for_each(|y| column[y] = pixels[y * w + x]);
+            box_blur_line(&mut column, radius, &mut scratch);
+            (0..h).for_each(|y| pixels[y * w + x] = column[y]);
+          }
+        }
+
+        for y in 0..h {
+          for (p, v) in buffer[index(0, y)..index(w, y)]
+            .chunks_exact_mut(4)
+            .zip(&pixels[y * w..(y + 1) * w])
+          {
+            p.copy_from_slice(&v.map(|c| c as u8));
+          }
+        }
+      }
+    }
+  }
+}
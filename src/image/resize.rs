@@ -0,0 +1,253 @@
+use crate::Image;
+use std::f32::consts::PI;
+
+/// Resampling filter used by [`Image::resize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+  Nearest,
+  /// Triangle filter, support of 1 pixel.
+  Bilinear,
+  /// Catmull-Rom cubic, support of 2 pixels.
+  CatmullRom,
+  /// Windowed sinc, support of 3 pixels. Sharpest and slowest.
+  Lanczos3,
+}
+
+impl Filter {
+  fn support(&self) -> f32 {
+    match self {
+      Filter::Nearest => 0.0,
+      Filter::Bilinear => 1.0,
+      Filter::CatmullRom => 2.0,
+      Filter::Lanczos3 => 3.0,
+    }
+  }
+
+  fn kernel(&self, x: f32) -> f32 {
+    let x = x.abs();
+    match self {
+      Filter::Nearest => 1.0,
+      Filter::Bilinear => (1.0 - x).max(0.0),
+      Filter::CatmullRom => {
+        if x < 1.0 {
+          1.5 * x * x * x - 2.5 * x * x + 1.0
+        } else if x < 2.0 {
+          -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+        } else {
+          0.0
+        }
+      }
+      Filter::Lanczos3 => {
+        if x < 3.0 {
+          sinc(x) * sinc(x / 3.0)
+        } else {
+          0.0
+        }
+      }
+    }
+  }
+}
+
+fn sinc(x: f32) -> f32 {
+  if x == 0.0 {
+    1.0
+  } else {
+    let x = x * PI;
+    x.sin() / x
+  }
+}
+
+/// 每个目标像素对应的源像素起点和权重
+struct Weights {
+  start: usize,
+  values: Vec<f32>,
+}
+
+fn weights(src: u32, dst: u32, filter: Filter) -> Vec<Weights> {
+  let scale = src as f32 / dst as f32;
+  let filter_scale = scale.max(1.0);
+  let support = filter.support() * filter_scale;
+
+  (0..dst)
+    .map(|i| {
+      let center = (i as f32 + 0.5) * scale;
+      let left = ((center - support).floor() as i64).clamp(0, src as i64 - 1) as usize;
+      let right = ((center + support).ceil() as i64).clamp(left as i64 + 1, src as i64) as usize;
+
+      let mut values: Vec<f32> = (left..right)
+        .map(|j| filter.kernel((j as f32 + 0.5 - center) / filter_scale))
+        .collect();
+
+      let sum: f32 = values.iter().sum();
+      if sum != 0.0 {
+        values.iter_mut().for_each(|v| *v /= sum);
+      }
+
+      Weights {
+        start: left,
+        values,
+      }
+    })
+    .collect()
+}
+
+fn nearest(image: &Image, width: u32, height: u32) -> Vec<u8> {
+  let scale_x = image.width() as f64 / width as f64;
+  let scale_y = image.height() as f64 / height as f64;
+  let src_x: Vec<usize> = (0..width)
+    .map(|x| (((x as f64 + 0.5) * scale_x) as usize).min(image.width() as usize - 1))
+    .collect();
+
+  let mut buffer = Vec::with_capacity(width as usize * height as usize * 4);
+  for y in 0..height {
+    let sy = (((y as f64 + 0.5) * scale_y) as u32).min(image.height() - 1);
+    let row = image.row(sy).unwrap_or_default();
+    for &sx in &src_x {
+      buffer.extend_from_slice(&row[sx * 4..sx * 4 + 4]);
+    }
+  }
+
+  buffer
+}
+
+// 在预乘 alpha 空间中做可分离卷积，避免透明边缘出现色晕
+fn convolve(image: &Image, width: u32, height: u32, filter: Filter) -> Vec<u8> {
+  let horizontal = weights(image.width(), width, filter);
+  let vertical = weights(image.height(), height, filter);
+  let dst_stride = width as usize * 4;
+
+  let mut tmp = vec![0f32; dst_stride * image.height() as usize];
+  for (src, dst) in image.rows().zip(tmp.chunks_exact_mut(dst_stride)) {
+    for (w, out) in horizontal.iter().zip(dst.chunks_exact_mut(4)) {
+      let mut acc = [0f32; 4];
+      for (k, &weight) in w.values.iter().enumerate() {
+        let p = &src[(w.start + k) * 4..(w.start + k) * 4 + 4];
+        let a = p[3] as f32 * weight;
+        acc[0] += p[0] as f32 * a;
+        acc[1] += p[1] as f32 * a;
+        acc[2] += p[2] as f32 * a;
+        acc[3] += a;
+      }
+      out.copy_from_slice(&acc);
+    }
+  }
+
+  let mut buffer = vec![0u8; dst_stride * height as usize];
+  let mut acc = vec![0f32; dst_stride];
+  for (w, dst) in vertical.iter().zip(buffer.chunks_exact_mut(dst_stride)) {
+    acc.iter_mut().for_each(|v| *v = 0.0);
+    for (k, &weight) in w.values.iter().enumerate() {
+      let start = (w.start + k) * dst_stride;
+      for (a, &t) in acc.iter_mut().zip(&tmp[start..start + dst_stride]) {
+        *a += t * weight;
+      }
+    }
+
+    for (p, out) in acc.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+      let alpha = p[3];
+      if alpha > 0.0 {
+        out[0] = (p[0] / alpha).round().clamp(0.0, 255.0) as u8;
+        out[1] = (p[1] / alpha).round().clamp(0.0, 255.0) as u8;
+        out[2] = (p[2] / alpha).round().clamp(0.0, 255.0) as u8;
+        out[3] = alpha.round().clamp(0.0, 255.0) as u8;
+      }
+    }
+  }
+
+  buffer
+}
+
+impl Image {
+  /// Resamples the image to `width` x `height`.
+  pub fn resize(&self, width: u32, height: u32, filter: Filter) -> Image {
+    if width == 0 || height == 0 || self.width() == 0 || self.height() == 0 {
+      let mut image =
+        self.with_buffer(width, height, vec![0; width as usize * height as usize * 4]);
+      image.metadata.scale_factor = None;
+      return image;
+    }
+
+    if width == self.width() && height == self.height() {
+      return self.clone();
+    }
+
+    let buffer = match filter {
+      Filter::Nearest => nearest(self, width, height),
+      _ => convolve(self, width, height, filter),
+    };
+
+    let mut image = self.with_buffer(width, height, buffer);
+    image.metadata.rescale(width as f32 / self.width() as f32);
+    image
+  }
+
+  /// Averages each `factor` x `factor` block into one pixel, e.g. `2` turns a
+  /// HiDPI capture into its logical size. Much faster than [`Image::resize`].
+  pub fn downscale(&self, factor: u32) -> Image {
+    if factor <= 1 || self.width() == 0 || self.height() == 0 {
+      return self.clone();
+    }
+
+    let width = self.width().div_ceil(factor);
+    let height = self.height().div_ceil(factor);
+    let factor = factor as usize;
+    let mut buffer = Vec::with_capacity(width as usize * height as usize * 4);
+    let mut acc = vec![0u64; width as usize * 4];
+    let mut count = vec![0u64; width as usize];
+
+    for block in self.as_raw().chunks(self.stride() * factor) {
+      acc.iter_mut().for_each(|v| *v = 0);
+      count.iter_mut().for_each(|v| *v = 0);
+
+      for row in block.chunks_exact(self.stride()) {
+        for (x, p) in row.chunks_exact(4).enumerate() {
+          let i = x / factor;
+          let a = p[3] as u64;
+          acc[i * 4] += p[0] as u64 * a;
+          acc[i * 4 + 1] += p[1] as u64 * a;
+          acc[i * 4 + 2] += p[2] as u64 * a;
+          acc[i * 4 + 3] += a;
+          count[i] += 1;
+        }
+      }
+
+      for (p, &n) in acc.chunks_exact(4).zip(&count) {
+        let alpha = p[3];
+        let unpremultiply = |v: u64| (v + alpha / 2).checked_div(alpha).unwrap_or(0) as u8;
+        buffer.extend_from_slice(&[
+          unpremultiply(p[0]),
+          unpremultiply(p[1]),
+          unpremultiply(p[2]),
+          ((alpha + n / 2) / n) as u8,
+        ]);
+      }
+    }
+
+    let mut image = self.with_buffer(width, height, buffer);
+    image.metadata.rescale(1.0 / factor as f32);
+    image
+  }
+
+  /// Scales the image down so its longest side is at most `max_dim`,
+  /// keeping the aspect ratio. Smaller images are returned unchanged.
+  pub fn thumbnail(&self, max_dim: u32) -> Image {
+    let longest = self.width().max(self.height());
+    if longest <= max_dim {
+      return self.clone();
+    }
+
+    let scale = max_dim as f64 / longest as f64;
+    let width = ((self.width() as f64 * scale).round() as u32).max(1);
+    let height = ((self.height() as f64 * scale).round() as u32).max(1);
+
+    // 先用盒式滤波做整数倍缩小，再对剩余部分做高质量重采样
+    let factor = (self.width() / width).min(self.height() / height);
+    if factor >= 2 {
+      self
+        .downscale(factor)
+        .resize(width, height, Filter::CatmullRom)
+    } else {
+      self.resize(width, height, Filter::CatmullRom)
+    }
+  }
+}
@@ -0,0 +1,68 @@
+use crate::Image;
+
+impl Image {
+  /// Rotates the image 90 degrees clockwise.
+  pub fn rotate90(&self) -> Image {
+    let (width, height) = (self.width() as usize, self.height() as usize);
+    let src = self.as_raw();
+    let mut buffer = vec![0u8; src.len()];
+
+    for y in 0..height {
+      for x in 0..width {
+        let i = (y * width + x) * 4;
+        let j = (x * height + (height - 1 - y)) * 4;
+        buffer[j..j + 4].copy_from_slice(&src[i..i + 4]);
+      }
+    }
+
+    self.with_buffer(self.height(), self.width(), buffer)
+  }
+
+  pub fn rotate180(&self) -> Image {
+    let mut buffer = Vec::with_capacity(self.as_raw().len());
+    for p in self.as_raw().chunks_exact(4).rev() {
+      buffer.extend_from_slice(p);
+    }
+
+    self.with_buffer(self.width(), self.height(), buffer)
+  }
+
+  /// Rotates the image 90 degrees counter-clockwise.
+  pub fn rotate270(&self) -> Image {
+    let (width, height) = (self.width() as usize, self.height() as usize);
+    let src = self.as_raw();
+    let mut buffer = vec![0u8; src.len()];
+
+    for y in 0..height {
+      for x in 0..width {
+        let i = (y * width + x) * 4;
+        let j = ((width - 1 - x) * height + y) * 4;
+        buffer[j..j + 4].copy_from_slice(&src[i..i + 4]);
+      }
+    }
+
+    self.with_buffer(self.height(), self.width(), buffer)
+  }
+
+  /// Mirrors the image left to right.
+  pub fn flip_horizontal(&self) -> Image {
+    let mut buffer = Vec::with_capacity(self.as_raw().len());
+    for row in self.rows() {
+      for p in row.chunks_exact(4).rev() {
+        buffer.extend_from_slice(p);
+      }
+    }
+
+    self.with_buffer(self.width(), self.height(), buffer)
+  }
+
+  /// Mirrors the image top to bottom.
+  pub fn flip_vertical(&self) -> Image {
+    let mut buffer = Vec::with_capacity(self.as_raw().len());
+    for row in self.rows().rev() {
+      buffer.extend_from_slice(row);
+    }
+
+    self.with_buffer(self.width(), self.height(), buffer)
+  }
+}
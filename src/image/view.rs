@@ -0,0 +1,99 @@
+use crate::{Image, Rect};
+use anyhow::{anyhow, Result};
+
+/// A zero-copy rectangular view into an [`Image`].
+#[derive(Debug, Clone, Copy)]
+pub struct SubImage<'a> {
+  image: &'a Image,
+  x: u32,
+  y: u32,
+  width: u32,
+  height: u32,
+}
+
+impl<'a> SubImage<'a> {
+  pub(crate) fn new(image: &'a Image, rect: Rect) -> Result<Self> {
+    let bounds = Rect::new(0, 0, image.width(), image.height());
+    let rect = rect
+      .intersection(&bounds)
+      .ok_or_else(|| anyhow!("Area size is invalid"))?;
+
+    Ok(SubImage {
+      image,
+      x: rect.x as u32,
+      y: rect.y as u32,
+      width: rect.width,
+      height: rect.height,
+    })
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// Position and size of the view inside the parent image.
+  pub fn bounds(&self) -> Rect {
+    Rect::new(self.x as i32, self.y as i32, self.width, self.height)
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+
+    self.image.pixel(self.x + x, self.y + y)
+  }
+
+  /// Returns the RGBA bytes of row `y`, borrowed from the parent image.
+  pub fn row(&self, y: u32) -> Option<&'a [u8]> {
+    if y >= self.height {
+      return None;
+    }
+
+    let start = self.x as usize * 4;
+    let end = start + self.width as usize * 4;
+    self
+      .image
+      .row(self.y + y)
+      .and_then(|row| row.get(start..end))
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+    let view = *self;
+    (0..self.height).filter_map(move |y| view.row(y))
+  }
+
+  /// A view into this view, `rect` is relative to the view.
+  pub fn view(&self, rect: Rect) -> Result<SubImage<'a>> {
+    let bounds = Rect::new(0, 0, self.width, self.height);
+    let rect = rect
+      .intersection(&bounds)
+      .ok_or_else(|| anyhow!("Area size is invalid"))?;
+
+    SubImage::new(
+      self.image,
+      Rect::new(
+        rect.x + self.x as i32,
+        rect.y + self.y as i32,
+        rect.width,
+        rect.height,
+      ),
+    )
+  }
+
+  /// Copies the view into a new image.
+  pub fn to_image(&self) -> Image {
+    let mut buffer = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+    for row in self.rows() {
+      buffer.extend_from_slice(row);
+    }
+
+    let mut image = self.image.with_buffer(self.width, self.height, buffer);
+    image.metadata.crop(self.bounds());
+    image
+  }
+}
@@ -1,8 +1,35 @@
 use anyhow::{anyhow, Result};
 pub use display_info::DisplayInfo;
 
+mod animation;
+pub mod annotate;
+mod desktop;
+mod encoder;
 mod image;
-pub use image::Image;
+mod options;
+mod record;
+mod rect;
+mod watermark;
+mod window;
+pub mod yuv;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use animation::Animation;
+pub use desktop::{
+  capture_desktop, capture_desktop_with, capture_rect, capture_rect_with, ScalePolicy,
+};
+pub use encoder::{AnimationFormat, ImageFormat, PngCompression, PngFilter, PngOptions};
+pub use image::{
+  AlphaMode, ColorProfile, DiffResult, DisplayMetadata, Filter, Image, ImageHash, Metadata,
+  Quantizer, RedactStyle, SubImage,
+};
+pub use options::{CaptureOptions, Orientation};
+pub use record::{Frame, RecordOptions, Recording};
+pub use rect::Rect;
+pub use watermark::{Corner, Watermark};
+pub use window::{capture_window, capture_window_with, WindowRule};
 
 #[cfg(target_os = "macos")]
 mod darwin;
@@ -19,6 +46,27 @@ mod linux;
 #[cfg(target_os = "linux")]
 use linux::*;
 
+/// Captures `area` of the screen, `None` for the whole screen, together
+/// with the top-level windows when `options` has window rules. X11 reads both
+/// on one connection while the server is grabbed.
+pub(crate) fn capture_raw(
+  display_info: &DisplayInfo,
+  area: Option<Rect>,
+  options: &CaptureOptions,
+) -> Result<(Image, Vec<window::WindowInfo>)> {
+  match (area, options.needs_windows()) {
+    (None, false) => Ok((capture_screen(display_info)?, Vec::new())),
+    (Some(area), false) => {
+      let image = capture_screen_area(display_info, area.x, area.y, area.width, area.height)?;
+      Ok((image, Vec::new()))
+    }
+    (area, true) => {
+      let area = area.unwrap_or_else(|| Rect::new(0, 0, display_info.width, display_info.height));
+      capture_screen_area_with_windows(display_info, area.x, area.y, area.width, area.height)
+    }
+  }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Screen {
   pub display_info: DisplayInfo,
@@ -42,7 +90,15 @@ impl Screen {
   }
 
   pub fn capture(&self) -> Result<Image> {
-    capture_screen(&self.display_info)
+    self.capture_with(&CaptureOptions::default())
+  }
+
+  pub fn capture_with(&self, options: &CaptureOptions) -> Result<Image> {
+    let display_info = self.display_info;
+    let area = Rect::new(0, 0, display_info.width, display_info.height);
+    let (image, windows) = capture_raw(&display_info, None, options)?;
+
+    options.apply(image, &windows, &display_info, area)
   }
 
   /**
@@ -50,6 +106,41 @@ impl Screen {
    * 区域x,y为相对于当前屏幕的x,y坐标
    */
   pub fn capture_area(&self, x: i32, y: i32, width: u32, height: u32) -> Result<Image> {
+    self.capture_area_with(x, y, width, height, &CaptureOptions::default())
+  }
+
+  pub fn capture_area_with(
+    &self,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    options: &CaptureOptions,
+  ) -> Result<Image> {
+    let display_info = self.display_info;
+    let area = self.clamp_area(x, y, width, height)?;
+    let (image, windows) = capture_raw(&display_info, Some(area), options)?;
+
+    options.apply(image, &windows, &display_info, area)
+  }
+
+  /// Captures frames at `options.fps` until `options.max_frames` is reached.
+  /// The backend connection is opened once and reused for every frame.
+  pub fn record(&self, options: RecordOptions) -> Result<Recording> {
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+      return Err(anyhow!("Invalid frame rate: {}", options.fps));
+    }
+
+    let area = match options.area {
+      Some(area) => self.clamp_area(area.x, area.y, area.width, area.height)?,
+      None => Rect::new(0, 0, self.display_info.width, self.display_info.height),
+    };
+
+    Recording::new(&self.display_info, area, options)
+  }
+
+  /// Limits an area relative to the screen to the screen's bounds.
+  fn clamp_area(&self, x: i32, y: i32, width: u32, height: u32) -> Result<Rect> {
     let display_info = self.display_info;
     let screen_x2 = display_info.x + display_info.width as i32;
     let screen_y2 = display_info.y + display_info.height as i32;
@@ -84,12 +175,11 @@ impl Screen {
       return Err(anyhow!("Area size is invalid"));
     }
 
-    capture_screen_area(
-      &display_info,
+    Ok(Rect::new(
       x1 - display_info.x,
       y1 - display_info.y,
       (x2 - x1) as u32,
       (y2 - y1) as u32,
-    )
+    ))
   }
 }
@@ -2,12 +2,12 @@ mod wayland;
 mod wayland_screenshot;
 mod xorg;
 
-use crate::{DisplayInfo, Image};
-use anyhow::Result;
+use crate::{window::WindowInfo, DisplayInfo, Image, Rect, WindowRule};
+use anyhow::{anyhow, Result};
 
 use std::env::var_os;
 use wayland::{wayland_capture_screen, wayland_capture_screen_area};
-use xorg::{xorg_capture_screen, xorg_capture_screen_area};
+use xorg::{xorg_capture_screen, xorg_capture_screen_area, xorg_capture_window, XorgRecorder};
 
 fn wayland_detect() -> bool {
   let xdg_session_type = var_os("XDG_SESSION_TYPE")
@@ -44,3 +44,83 @@ pub fn capture_screen_area(
     xorg_capture_screen_area(display_info, x, y, width, height)
   }
 }
+
+/// Repeated captures of one area. The X11 connection is kept open, Wayland
+/// goes through a full screenshot request for every frame.
+pub enum Recorder {
+  Xorg(XorgRecorder),
+  Wayland {
+    display_info: DisplayInfo,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+  },
+}
+
+impl Recorder {
+  pub fn new(display_info: &DisplayInfo, x: i32, y: i32, width: u32, height: u32) -> Result<Self> {
+    if wayland_detect() {
+      Ok(Recorder::Wayland {
+        display_info: *display_info,
+        x,
+        y,
+        width,
+        height,
+      })
+    } else {
+      Ok(Recorder::Xorg(XorgRecorder::new(
+        display_info,
+        x,
+        y,
+        width,
+        height,
+      )?))
+    }
+  }
+
+  pub fn capture(&mut self) -> Result<Image> {
+    match self {
+      Recorder::Xorg(recorder) => recorder.capture(),
+      Recorder::Wayland {
+        display_info,
+        x,
+        y,
+        width,
+        height,
+      } => wayland_capture_screen_area(display_info, *x, *y, *width, *height),
+    }
+  }
+
+  pub fn capture_with_windows(&mut self) -> Result<(Image, Vec<WindowInfo>)> {
+    match self {
+      Recorder::Xorg(recorder) => recorder.capture_with_windows(),
+      Recorder::Wayland { .. } => Err(anyhow!("Listing windows is not supported on Wayland")),
+    }
+  }
+}
+
+/// Same as `capture_screen_area`, together with the top-level windows read
+/// on the same X11 connection.
+pub fn capture_screen_area_with_windows(
+  display_info: &DisplayInfo,
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+) -> Result<(Image, Vec<WindowInfo>)> {
+  Recorder::new(display_info, x, y, width, height)?.capture_with_windows()
+}
+
+/// The topmost window matching `rule`, its rect in physical desktop pixels
+/// and the top-level windows listed with it.
+pub fn capture_raw_window(
+  rule: &WindowRule,
+  keep_alpha: bool,
+) -> Result<(Image, Rect, Vec<WindowInfo>)> {
+  if wayland_detect() {
+    Err(anyhow!("Capturing windows is not supported on Wayland"))
+  } else {
+    xorg_capture_window(rule, keep_alpha)
+  }
+}
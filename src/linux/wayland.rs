@@ -7,9 +7,7 @@ pub fn wayland_capture_screen(display_info: &DisplayInfo) -> Result<Image> {
   let width = (display_info.width as f32) * display_info.scale_factor;
   let height = (display_info.height as f32) * display_info.scale_factor;
 
-  let buffer = wayland_screenshot(x, y, width as i32, height as i32)?;
-
-  Ok(Image::new(width as u32, height as u32, buffer))
+  wayland_screenshot(x, y, width as i32, height as i32)
 }
 
 pub fn wayland_capture_screen_area(
@@ -24,6 +22,5 @@ pub fn wayland_capture_screen_area(
   let area_width = (width as f32) * display_info.scale_factor;
   let area_height = (height as f32) * display_info.scale_factor;
 
-  let buffer = wayland_screenshot(area_x, area_y, area_width as i32, area_height as i32)?;
-  Ok(Image::new(width as u32, height as u32, buffer))
+  wayland_screenshot(area_x, area_y, area_width as i32, area_height as i32)
 }
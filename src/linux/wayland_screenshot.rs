@@ -1,14 +1,14 @@
+use crate::Image;
 use anyhow::{anyhow, Result};
 use dbus::{
   arg::{AppendAll, Iter, IterAppend, PropMap, ReadAll, RefArg, TypeMismatchError, Variant},
   blocking::Connection,
   message::{MatchRule, SignalArgs},
 };
-use png::{BitDepth, ColorType, Decoder, Encoder};
 use std::{
   collections::HashMap,
   env::temp_dir,
-  fs::{self, File},
+  fs,
   sync::{Arc, Mutex},
   time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -46,7 +46,7 @@ fn org_gnome_shell_screenshot(
   y: i32,
   width: i32,
   height: i32,
-) -> Result<Vec<u8>> {
+) -> Result<Image> {
   let proxy = conn.with_proxy(
     "org.gnome.Shell.Screenshot",
     "/org/gnome/Shell/Screenshot",
@@ -64,7 +64,7 @@ fn org_gnome_shell_screenshot(
 
   let filename = path.to_string_lossy().to_string();
 
-  proxy.method_call(
+  proxy.method_call::<(), _, _, _>(
     "org.gnome.Shell.Screenshot",
     "ScreenshotArea",
     (x, y, width, height, false, &filename),
@@ -73,7 +73,10 @@ fn org_gnome_shell_screenshot(
   let buffer = fs::read(&filename)?;
   fs::remove_file(&filename)?;
 
-  Ok(buffer)
+  let mut image = Image::from_png(&buffer)?;
+  image.metadata_mut().backend = Some("gnome-shell".to_string());
+
+  Ok(image)
 }
 
 fn org_freedesktop_portal_screenshot(
@@ -82,7 +85,7 @@ fn org_freedesktop_portal_screenshot(
   y: i32,
   width: i32,
   height: i32,
-) -> Result<Vec<u8>> {
+) -> Result<Image> {
   let status: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
   let status_res = status.clone();
   let path: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
@@ -119,7 +122,7 @@ fn org_freedesktop_portal_screenshot(
   options.insert(String::from("modal"), Variant(Box::new(true)));
   options.insert(String::from("interactive"), Variant(Box::new(false)));
 
-  proxy.method_call(
+  proxy.method_call::<(), _, _, _>(
     "org.freedesktop.portal.Screenshot",
     "Screenshot",
     ("", options),
@@ -154,15 +157,8 @@ fn org_freedesktop_portal_screenshot(
     return Err(anyhow!("Screenshot failed or canceled",));
   }
 
-  let decoder = Decoder::new(File::open(path)?);
-
-  let mut reader = decoder.read_info()?;
-  // Allocate the output buffer.
-  let mut buf = vec![0u8; reader.output_buffer_size()];
-  // Read the next frame. An APNG might contain multiple frames.
-  let info = reader.next_frame(&mut buf)?;
-  // Grab the bytes of the image.
-  let bytes = &buf[..info.buffer_size()];
+  let image = Image::from_png(&fs::read(path)?)?;
+  let bytes = image.as_raw();
 
   fs::remove_file(path)?;
 
@@ -171,7 +167,7 @@ fn org_freedesktop_portal_screenshot(
   for r in y..(y + height) {
     for c in x..(x + width) {
       let index = (((r - y) * width + (c - x)) * 4) as usize;
-      let i = ((r * info.width as i32 + c) * 4) as usize;
+      let i = ((r * image.width() as i32 + c) * 4) as usize;
       // 防止获取到的图片尺寸小于指定大小而 panic
       rgba[index] = bytes.get(i).copied().unwrap_or(0);
       rgba[index + 1] = bytes.get(i + 1).copied().unwrap_or(0);
@@ -180,23 +176,20 @@ fn org_freedesktop_portal_screenshot(
     }
   }
 
-  let mut buffer = Vec::new();
-
-  let mut encoder = Encoder::new(&mut buffer, width as u32, height as u32);
-  encoder.set_color(ColorType::Rgba);
-  encoder.set_depth(BitDepth::Eight);
+  let mut image = image.with_buffer(width as u32, height as u32, rgba);
+  image.metadata_mut().backend = Some("xdg-portal".to_string());
 
-  let mut writer = encoder.write_header()?;
-  writer.write_image_data(&rgba)?;
-  writer.finish()?;
-
-  Ok(buffer)
+  Ok(image)
 }
 
 // TODO: 失败后尝试删除文件
-pub fn wayland_screenshot(x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>> {
+pub fn wayland_screenshot(x: i32, y: i32, width: i32, height: i32) -> Result<Image> {
   let conn = Connection::new_session()?;
 
-  org_gnome_shell_screenshot(&conn, x, y, width, height)
-    .or_else(|_| org_freedesktop_portal_screenshot(&conn, x, y, width, height))
+  let mut image = org_gnome_shell_screenshot(&conn, x, y, width, height)
+    .or_else(|_| org_freedesktop_portal_screenshot(&conn, x, y, width, height))?;
+  // PNG 可能带有 alpha 通道，屏幕截图始终不透明
+  image.set_opaque();
+
+  Ok(image)
 }
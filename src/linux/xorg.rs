@@ -1,31 +1,147 @@
-use crate::{DisplayInfo, Image};
+use crate::{window::WindowInfo, AlphaMode, ColorProfile, DisplayInfo, Image, Rect, WindowRule};
 use anyhow::{anyhow, Result};
-use xcb::x::{Drawable, GetImage, ImageFormat};
+use xcb::{
+  composite,
+  x::{
+    Atom, Drawable, FreePixmap, GetGeometry, GetImage, GetProperty, GetWindowAttributes,
+    GrabServer, ImageFormat, InternAtom, MapState, Pixmap, QueryTree, UngrabServer, Window,
+    ATOM_ANY, ATOM_NONE, ATOM_WM_CLASS, ATOM_WM_NAME,
+  },
+  xinerama,
+};
 
-fn capture(x: i32, y: i32, width: u32, height: u32) -> Result<Image> {
-  let (conn, index) = xcb::Connection::connect(None)?;
+/// Connection and color profile kept between captures of the same area.
+pub struct XorgRecorder {
+  conn: xcb::Connection,
+  root: Window,
+  profile: Option<ColorProfile>,
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+}
 
-  let setup = conn.get_setup();
-  let screen = setup
-    .roots()
-    .nth(index as usize)
-    .ok_or_else(|| anyhow!("Not found screen"))?;
+impl XorgRecorder {
+  fn connect(display_info: &DisplayInfo, x: i32, y: i32, width: u32, height: u32) -> Result<Self> {
+    let (conn, index) =
+      xcb::Connection::connect_with_extensions(None, &[], &[xcb::Extension::Xinerama])?;
 
-  let get_image_cookie = conn.send_request(&GetImage {
-    format: ImageFormat::ZPixmap,
-    drawable: Drawable::Window(screen.root()),
-    x: x as i16,
-    y: y as i16,
-    width: width as u16,
-    height: height as u16,
-    plane_mask: u32::MAX,
-  });
+    let setup = conn.get_setup();
+    let root = setup
+      .roots()
+      .nth(index as usize)
+      .ok_or_else(|| anyhow!("Not found screen"))?
+      .root();
+    let profile = icc_profile(&conn, root, display_info).map(ColorProfile::Icc);
+
+    Ok(XorgRecorder {
+      conn,
+      root,
+      profile,
+      x,
+      y,
+      width,
+      height,
+    })
+  }
+
+  /// `x`, `y`, `width` and `height` are logical and relative to the display,
+  /// like `xorg_capture_screen_area`.
+  pub fn new(display_info: &DisplayInfo, x: i32, y: i32, width: u32, height: u32) -> Result<Self> {
+    let area_x = (((x + display_info.x) as f32) * display_info.scale_factor) as i32;
+    let area_y = (((y + display_info.y) as f32) * display_info.scale_factor) as i32;
+    let area_width = ((width as f32) * display_info.scale_factor) as u32;
+    let area_height = ((height as f32) * display_info.scale_factor) as u32;
+
+    XorgRecorder::connect(display_info, area_x, area_y, area_width, area_height)
+  }
+
+  pub fn capture(&self) -> Result<Image> {
+    let get_image_cookie = self.conn.send_request(&GetImage {
+      format: ImageFormat::ZPixmap,
+      drawable: Drawable::Window(self.root),
+      x: self.x as i16,
+      y: self.y as i16,
+      width: self.width as u16,
+      height: self.height as u16,
+      plane_mask: u32::MAX,
+    });
+
+    let get_image_reply = self.conn.wait_for_reply(get_image_cookie)?;
+    // 根窗口截图是屏幕最终画面，32 位深度时 alpha 字节也没有意义，
+    // 按预乘格式还原反而会把颜色清成黑色
+    let mut image = Image::from_bgra_slice(
+      get_image_reply.data(),
+      self.width,
+      self.height,
+      (self.width as usize) * 4,
+      AlphaMode::Ignore,
+    )?;
+    image.metadata_mut().backend = Some("x11".to_string());
+    image.set_color_profile(self.profile.clone());
+
+    Ok(image)
+  }
+
+  /// Lists the top-level windows and captures on this connection while the
+  /// server is grabbed, so no window can be mapped, moved or raised in
+  /// between.
+  pub fn capture_with_windows(&self) -> Result<(Image, Vec<WindowInfo>)> {
+    self.conn.send_and_check_request(&GrabServer {})?;
+    let windows = xorg_windows(&self.conn, self.root);
+    let image = self.capture();
+    self.conn.send_and_check_request(&UngrabServer {})?;
+
+    Ok((image?, windows?))
+  }
+}
+
+// 按 ICC Profiles in X 约定，第 n 个屏幕的配置文件存放在根窗口的 _ICC_PROFILE_n 属性上
+fn icc_profile(
+  conn: &xcb::Connection,
+  root: Window,
+  display_info: &DisplayInfo,
+) -> Option<Vec<u8>> {
+  let index = xinerama_index(conn, display_info)?;
+
+  let name = match index {
+    0 => "_ICC_PROFILE".to_string(),
+    n => format!("_ICC_PROFILE_{n}"),
+  };
+  let atom = intern_atom(conn, name.as_bytes()).ok()?;
+  let profile = get_property(conn, root, atom, ICC_PROFILE_MAX_LENGTH).ok()?;
+
+  (!profile.is_empty()).then_some(profile)
+}
+
+// 没有 Xinerama 时整个 X 屏幕就是第 0 个屏幕，否则取包含显示器中心点的 Xinerama 屏幕
+fn xinerama_index(conn: &xcb::Connection, display_info: &DisplayInfo) -> Option<usize> {
+  if !conn
+    .active_extensions()
+    .any(|extension| extension == xcb::Extension::Xinerama)
+  {
+    return Some(0);
+  }
 
-  let get_image_reply = conn.wait_for_reply(get_image_cookie)?;
-  let bytes = Vec::from(get_image_reply.data());
+  let cookie = conn.send_request(&xinerama::QueryScreens {});
+  let reply = conn.wait_for_reply(cookie).ok()?;
+  if reply.screen_info().is_empty() {
+    return Some(0);
+  }
 
-  let image = Image::from_bgra(bytes, width, height, (width as usize) * 4)?;
-  Ok(image)
+  let center_x = (((display_info.x as f32) + (display_info.width as f32) / 2.0)
+    * display_info.scale_factor) as i32;
+  let center_y = (((display_info.y as f32) + (display_info.height as f32) / 2.0)
+    * display_info.scale_factor) as i32;
+  reply.screen_info().iter().position(|screen| {
+    Rect::new(
+      screen.x_org as i32,
+      screen.y_org as i32,
+      screen.width as u32,
+      screen.height as u32,
+    )
+    .contains(center_x, center_y)
+  })
 }
 
 pub fn xorg_capture_screen(display_info: &DisplayInfo) -> Result<Image> {
@@ -34,7 +150,7 @@ pub fn xorg_capture_screen(display_info: &DisplayInfo) -> Result<Image> {
   let width = ((display_info.width as f32) * display_info.scale_factor) as u32;
   let height = ((display_info.height as f32) * display_info.scale_factor) as u32;
 
-  capture(x, y, width, height)
+  XorgRecorder::connect(display_info, x, y, width, height)?.capture()
 }
 
 pub fn xorg_capture_screen_area(
@@ -44,10 +160,339 @@ pub fn xorg_capture_screen_area(
   width: u32,
   height: u32,
 ) -> Result<Image> {
-  let area_x = (((x + display_info.x) as f32) * display_info.scale_factor) as i32;
-  let area_y = (((y + display_info.y) as f32) * display_info.scale_factor) as i32;
-  let area_width = ((width as f32) * display_info.scale_factor) as u32;
-  let area_height = ((height as f32) * display_info.scale_factor) as u32;
+  XorgRecorder::new(display_info, x, y, width, height)?.capture()
+}
+
+// 以 4 字节为单位，ICC 配置文件最大 16MB
+const ICC_PROFILE_MAX_LENGTH: u32 = 4 * 1024 * 1024;
+
+fn intern_atom(conn: &xcb::Connection, name: &[u8]) -> Result<Atom> {
+  let cookie = conn.send_request(&InternAtom {
+    only_if_exists: true,
+    name,
+  });
+  Ok(conn.wait_for_reply(cookie)?.atom())
+}
+
+fn get_property(
+  conn: &xcb::Connection,
+  window: Window,
+  property: Atom,
+  long_length: u32,
+) -> xcb::Result<Vec<u8>> {
+  if property == ATOM_NONE {
+    return Ok(Vec::new());
+  }
+
+  let cookie = conn.send_request(&GetProperty {
+    delete: false,
+    window,
+    property,
+    r#type: ATOM_ANY,
+    long_offset: 0,
+    long_length,
+  });
+  let reply = conn.wait_for_reply(cookie)?;
+
+  if reply.format() == 8 {
+    Ok(reply.value::<u8>().to_vec())
+  } else {
+    Ok(Vec::new())
+  }
+}
+
+// 窗口管理器会给应用窗口套一层框架窗口，WM_STATE 所在的子窗口才是应用窗口
+fn find_client(
+  conn: &xcb::Connection,
+  window: Window,
+  wm_state: Atom,
+  depth: u32,
+) -> Option<Window> {
+  let cookie = conn.send_request(&GetProperty {
+    delete: false,
+    window,
+    property: wm_state,
+    r#type: ATOM_ANY,
+    long_offset: 0,
+    long_length: 0,
+  });
+  if conn.wait_for_reply(cookie).ok()?.r#type() != ATOM_NONE {
+    return Some(window);
+  }
+
+  if depth == 0 {
+    return None;
+  }
+
+  let cookie = conn.send_request(&QueryTree { window });
+  let reply = conn.wait_for_reply(cookie).ok()?;
+  reply
+    .children()
+    .iter()
+    .find_map(|&child| find_client(conn, child, wm_state, depth - 1))
+}
+
+struct Atoms {
+  wm_state: Atom,
+  net_wm_name: Atom,
+  net_wm_window_opacity: Atom,
+  gtk_frame_extents: Atom,
+}
+
+struct TopLevel {
+  window: Window,
+  rect: Rect,
+  /// Part that hides the windows below it, `None` for translucent windows.
+  opaque: Option<Rect>,
+  info: WindowInfo,
+}
+
+// 枚举期间窗口可能已被销毁，这类错误跳过该窗口而不是让整个截图失败
+fn is_gone(err: &xcb::Error) -> bool {
+  matches!(
+    err,
+    xcb::Error::Protocol(xcb::ProtocolError::X(
+      xcb::x::Error::Window(_) | xcb::x::Error::Drawable(_),
+      _
+    ))
+  )
+}
+
+fn get_cardinals(
+  conn: &xcb::Connection,
+  window: Window,
+  property: Atom,
+  long_length: u32,
+) -> xcb::Result<Vec<u32>> {
+  if property == ATOM_NONE {
+    return Ok(Vec::new());
+  }
+
+  let cookie = conn.send_request(&GetProperty {
+    delete: false,
+    window,
+    property,
+    r#type: ATOM_ANY,
+    long_offset: 0,
+    long_length,
+  });
+  let reply = conn.wait_for_reply(cookie)?;
+
+  if reply.format() == 32 {
+    Ok(reply.value::<u32>().to_vec())
+  } else {
+    Ok(Vec::new())
+  }
+}
+
+fn query_window(
+  conn: &xcb::Connection,
+  window: Window,
+  atoms: &Atoms,
+) -> xcb::Result<Option<TopLevel>> {
+  let cookie = conn.send_request(&GetWindowAttributes { window });
+  if conn.wait_for_reply(cookie)?.map_state() != MapState::Viewable {
+    return Ok(None);
+  }
+
+  let cookie = conn.send_request(&GetGeometry {
+    drawable: Drawable::Window(window),
+  });
+  let geometry = conn.wait_for_reply(cookie)?;
+  let border = geometry.border_width() as u32 * 2;
+  let rect = Rect::new(
+    geometry.x() as i32,
+    geometry.y() as i32,
+    geometry.width() as u32 + border,
+    geometry.height() as u32 + border,
+  );
+
+  let client = find_client(conn, window, atoms.wm_state, 2).unwrap_or(window);
+  let classes: Vec<String> = get_property(conn, client, ATOM_WM_CLASS, 1024)?
+    .split(|&b| b == 0)
+    .filter(|class| !class.is_empty())
+    .map(|class| String::from_utf8_lossy(class).to_string())
+    .collect();
+  let mut title = get_property(conn, client, atoms.net_wm_name, 1024)?;
+  if title.is_empty() {
+    title = get_property(conn, client, ATOM_WM_NAME, 1024)?;
+  }
+  let title = String::from_utf8_lossy(&title).to_string();
+
+  // ARGB visual 或设置了不透明度的窗口可能透出下面的窗口，不能用来遮挡
+  let mut translucent = geometry.depth() == 32;
+  if client != window {
+    let cookie = conn.send_request(&GetGeometry {
+      drawable: Drawable::Window(client),
+    });
+    translucent |= conn.wait_for_reply(cookie)?.depth() == 32;
+  }
+  for target in [window, client] {
+    let opacity = get_cardinals(conn, target, atoms.net_wm_window_opacity, 1)?;
+    translucent |= opacity.first().is_some_and(|&opacity| opacity != u32::MAX);
+  }
+
+  // 客户端装饰的阴影边距是透明的，只有去掉边距后的部分才遮挡下面的窗口
+  let opaque = if translucent {
+    None
+  } else {
+    match get_cardinals(conn, client, atoms.gtk_frame_extents, 4)?[..] {
+      [left, right, top, bottom] => Some(Rect::new(
+        rect.x.saturating_add(left as i32),
+        rect.y.saturating_add(top as i32),
+        rect.width.saturating_sub(left.saturating_add(right)),
+        rect.height.saturating_sub(top.saturating_add(bottom)),
+      )),
+      _ => Some(rect),
+    }
+    .filter(|rect| !rect.is_empty())
+  };
+
+  Ok(Some(TopLevel {
+    window,
+    rect,
+    opaque,
+    info: WindowInfo {
+      classes,
+      title,
+      visible: Vec::new(),
+    },
+  }))
+}
+
+/// Viewable top-level windows, bottom to top, with the parts not covered by
+/// opaque windows stacked above them in root window coordinates. Windows
+/// with an ARGB visual or an opacity below 100% and the client-side shadow
+/// margins (`_GTK_FRAME_EXTENTS`) never count as covering.
+fn xorg_windows(conn: &xcb::Connection, root: Window) -> Result<Vec<WindowInfo>> {
+  let windows = top_levels(conn, root)?;
+  Ok(windows.into_iter().map(|window| window.info).collect())
+}
+
+fn top_levels(conn: &xcb::Connection, root: Window) -> Result<Vec<TopLevel>> {
+  let atoms = Atoms {
+    wm_state: intern_atom(conn, b"WM_STATE")?,
+    net_wm_name: intern_atom(conn, b"_NET_WM_NAME")?,
+    net_wm_window_opacity: intern_atom(conn, b"_NET_WM_WINDOW_OPACITY")?,
+    gtk_frame_extents: intern_atom(conn, b"_GTK_FRAME_EXTENTS")?,
+  };
+
+  // QueryTree 按堆叠顺序从下到上返回子窗口
+  let cookie = conn.send_request(&QueryTree { window: root });
+  let tree = conn.wait_for_reply(cookie)?;
+
+  let mut windows: Vec<TopLevel> = Vec::new();
+  for &window in tree.children() {
+    match query_window(conn, window, &atoms) {
+      Ok(Some(top_level)) => windows.push(top_level),
+      Ok(None) => {}
+      Err(err) if is_gone(&err) => {}
+      Err(err) => return Err(err.into()),
+    }
+  }
+
+  let opaque: Vec<Option<Rect>> = windows.iter().map(|window| window.opaque).collect();
+  for (i, window) in windows.iter_mut().enumerate() {
+    let mut visible = vec![window.rect];
+    for above in opaque[i + 1..].iter().flatten() {
+      visible = visible
+        .iter()
+        .flat_map(|part| part.subtract(above))
+        .collect();
+    }
+    window.info.visible = visible;
+  }
+
+  Ok(windows)
+}
+
+/// Captures the topmost window matching `rule` from its Composite pixmap,
+/// covered parts included. Returns the image, the window's rect in root
+/// window coordinates and the top-level windows listed with it, all read
+/// while the server is grabbed.
+pub fn xorg_capture_window(
+  rule: &WindowRule,
+  keep_alpha: bool,
+) -> Result<(Image, Rect, Vec<WindowInfo>)> {
+  let (conn, index) =
+    xcb::Connection::connect_with_extensions(None, &[xcb::Extension::Composite], &[])?;
+
+  let setup = conn.get_setup();
+  let root = setup
+    .roots()
+    .nth(index as usize)
+    .ok_or_else(|| anyhow!("Not found screen"))?
+    .root();
+
+  // NameWindowPixmap 需要 Composite 0.2 以上
+  let cookie = conn.send_request(&composite::QueryVersion {
+    client_major_version: 0,
+    client_minor_version: 4,
+  });
+  conn.wait_for_reply(cookie)?;
+
+  conn.send_and_check_request(&GrabServer {})?;
+  let result = capture_top_level(&conn, root, rule, keep_alpha);
+  conn.send_and_check_request(&UngrabServer {})?;
+
+  result
+}
+
+fn capture_top_level(
+  conn: &xcb::Connection,
+  root: Window,
+  rule: &WindowRule,
+  keep_alpha: bool,
+) -> Result<(Image, Rect, Vec<WindowInfo>)> {
+  let mut windows = top_levels(conn, root)?;
+  let target = windows
+    .iter_mut()
+    .rev()
+    .find(|window| rule.matches(&window.info))
+    .ok_or_else(|| anyhow!("Not found window matching {:?}", rule))?;
+  // 截取的是窗口自身的内容，被遮挡的部分也在图片里
+  target.info.visible = vec![target.rect];
+  let (window, rect) = (target.window, target.rect);
+
+  // 自动重定向让服务器保留窗口的离屏内容，合成器已重定向时只是多一个引用
+  conn.send_and_check_request(&composite::RedirectWindow {
+    window,
+    update: composite::Redirect::Automatic,
+  })?;
+  let pixmap: Pixmap = conn.generate_id();
+  conn.send_request(&composite::NameWindowPixmap { window, pixmap });
+  let cookie = conn.send_request(&GetImage {
+    format: ImageFormat::ZPixmap,
+    drawable: Drawable::Pixmap(pixmap),
+    x: 0,
+    y: 0,
+    width: rect.width as u16,
+    height: rect.height as u16,
+    plane_mask: u32::MAX,
+  });
+  let reply = conn.wait_for_reply(cookie);
+  conn.send_request(&FreePixmap { pixmap });
+  conn.send_and_check_request(&composite::UnredirectWindow {
+    window,
+    update: composite::Redirect::Automatic,
+  })?;
+  let reply = reply?;
+
+  // ARGB 窗口为预乘 alpha，不保留 alpha 时直接忽略，先还原再丢弃会把颜色清成黑色
+  let alpha = if keep_alpha && reply.depth() == 32 {
+    AlphaMode::Premultiplied
+  } else {
+    AlphaMode::Ignore
+  };
+  let mut image = Image::from_bgra_slice(
+    reply.data(),
+    rect.width,
+    rect.height,
+    (rect.width as usize) * 4,
+    alpha,
+  )?;
+  image.metadata_mut().backend = Some("x11".to_string());
 
-  capture(area_x, area_y, area_width, area_height)
+  let windows = windows.into_iter().map(|window| window.info).collect();
+  Ok((image, rect, windows))
 }
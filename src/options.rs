@@ -0,0 +1,282 @@
+use crate::{
+  window::WindowInfo, ColorProfile, DisplayInfo, Image, PngOptions, Rect, RedactStyle, Watermark,
+  WindowRule,
+};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+
+/// Orientation of a captured image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+  /// As the user sees the screen, the backends capture this way.
+  #[default]
+  Display,
+  /// As the display is scanned out, undoing `DisplayInfo::rotation`.
+  Framebuffer,
+}
+
+#[derive(Default)]
+struct WindowRects {
+  redactions: Vec<(Rect, RedactStyle)>,
+  masks: Vec<Rect>,
+}
+
+/// Settings applied to a capture by `Screen::capture_with` and `Screen::capture_area_with`.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureOptions {
+  png: PngOptions,
+  orientation: Orientation,
+  redactions: Vec<(Rect, RedactStyle)>,
+  window_redactions: Vec<(WindowRule, RedactStyle)>,
+  watermark: Option<Watermark>,
+  keep_alpha: bool,
+  masks: Vec<Rect>,
+  window_masks: Vec<WindowRule>,
+}
+
+impl CaptureOptions {
+  pub fn new() -> Self {
+    CaptureOptions::default()
+  }
+
+  /// PNG settings attached to the captured image.
+  pub fn png(mut self, png: PngOptions) -> Self {
+    self.png = png;
+    self
+  }
+
+  /// Stitched captures (`capture_desktop_with`, `capture_rect_with`) only
+  /// support `Orientation::Display`.
+  pub fn orientation(mut self, orientation: Orientation) -> Self {
+    self.orientation = orientation;
+    self
+  }
+
+  /// Hides `rects` before the image is returned. Rects use the same
+  /// coordinates as `Screen::capture_area`, relative to the screen, or
+  /// global desktop coordinates like `capture_rect` for stitched captures.
+  pub fn redact(mut self, rects: Vec<Rect>, style: RedactStyle) -> Self {
+    self
+      .redactions
+      .extend(rects.into_iter().map(|rect| (rect, style)));
+    self
+  }
+
+  /// Hides the visible part of every window matching one of `rules`, looked
+  /// up again on each capture on the same connection while the X server is
+  /// grabbed, so windows cannot change in between. Only supported on X11;
+  /// captures fail elsewhere rather than leaking the windows.
+  pub fn redact_windows(mut self, rules: Vec<WindowRule>, style: RedactStyle) -> Self {
+    self
+      .window_redactions
+      .extend(rules.into_iter().map(|rule| (rule, style)));
+    self
+  }
+
+  /// Keeps the alpha channel of ARGB windows captured by
+  /// `capture_window_with` instead of returning opaque pixels. Screen
+  /// captures are always opaque.
+  pub fn keep_alpha(mut self, keep_alpha: bool) -> Self {
+    self.keep_alpha = keep_alpha;
+    self
+  }
+
+  /// Makes everything outside `rects` transparent. Rects use the same
+  /// coordinates as `redact`.
+  pub fn mask(mut self, rects: Vec<Rect>) -> Self {
+    self.masks.extend(rects);
+    self
+  }
+
+  /// Makes everything outside the visible parts of the windows matching
+  /// `rules` transparent, e.g. to cut a window out of a screen capture.
+  /// Window lookup has the same platform support as `redact_windows`.
+  pub fn mask_windows(mut self, rules: Vec<WindowRule>) -> Self {
+    self.window_masks.extend(rules);
+    self
+  }
+
+  /// Stamps `watermark` onto the capture, after redaction and rotation.
+  pub fn watermark(mut self, watermark: Watermark) -> Self {
+    self.watermark = Some(watermark);
+    self
+  }
+
+  /// Screens of a stitched capture can have different rotations, so it is
+  /// always returned as displayed.
+  pub(crate) fn check_stitched(&self) -> Result<()> {
+    if self.orientation == Orientation::Framebuffer {
+      return Err(anyhow!(
+        "Orientation::Framebuffer is not supported for stitched captures"
+      ));
+    }
+    Ok(())
+  }
+
+  pub(crate) fn keeps_alpha(&self) -> bool {
+    self.keep_alpha
+  }
+
+  /// Whether the capture has to list the top-level windows, see
+  /// `capture_screen_area_with_windows`.
+  pub(crate) fn needs_windows(&self) -> bool {
+    !self.window_redactions.is_empty() || !self.window_masks.is_empty()
+  }
+
+  /// Visible parts of the windows matched by the redaction and mask rules,
+  /// as screen-relative logical rects.
+  fn window_rects(&self, windows: &[WindowInfo], display_info: &DisplayInfo) -> WindowRects {
+    let mut rects = WindowRects::default();
+    let scale = display_info.scale_factor;
+    for window in windows {
+      let style = self
+        .window_redactions
+        .iter()
+        .find(|(rule, _)| rule.matches(window))
+        .map(|(_, style)| *style);
+      let masked = self.window_masks.iter().any(|rule| rule.matches(window));
+
+      // 窗口坐标为物理像素的全局坐标，转换为相对屏幕的逻辑坐标
+      for rect in &window.visible {
+        let x1 = (rect.x as f32 / scale).floor() as i32 - display_info.x;
+        let y1 = (rect.y as f32 / scale).floor() as i32 - display_info.y;
+        let x2 = (rect.right() as f32 / scale).ceil() as i32 - display_info.x;
+        let y2 = (rect.bottom() as f32 / scale).ceil() as i32 - display_info.y;
+        let rect = Rect::new(x1, y1, (x2 - x1) as u32, (y2 - y1) as u32);
+
+        if let Some(style) = style {
+          rects.redactions.push((rect, style));
+        }
+        if masked {
+          rects.masks.push(rect);
+        }
+      }
+    }
+
+    rects
+  }
+
+  /// `area` is the captured part of the screen in logical coordinates,
+  /// `windows` the top-level windows listed with it.
+  pub(crate) fn apply(
+    &self,
+    image: Image,
+    windows: &[WindowInfo],
+    display_info: &DisplayInfo,
+    area: Rect,
+  ) -> Result<Image> {
+    let mut image = self.apply_with_origin(image, windows, display_info, area, (0, 0))?;
+
+    if self.orientation == Orientation::Framebuffer {
+      // rotation 为顺时针角度，逆向旋转还原
+      image = match (display_info.rotation.round() as i32).rem_euclid(360) {
+        90 => image.rotate270(),
+        180 => image.rotate180(),
+        270 => image.rotate90(),
+        _ => image,
+      };
+    }
+
+    let scale = image.metadata().scale_factor.unwrap_or(1.0);
+    self.finish(&mut image, display_info, scale)?;
+    Ok(image)
+  }
+
+  /// Redacts and masks one screen of a stitched capture, where rects are
+  /// global desktop coordinates. The watermark is left to `finish`, so it is
+  /// stamped once on the stitched image.
+  pub(crate) fn apply_stitched(
+    &self,
+    image: Image,
+    windows: &[WindowInfo],
+    display_info: &DisplayInfo,
+    area: Rect,
+  ) -> Result<Image> {
+    let origin = (display_info.x, display_info.y);
+    self.apply_with_origin(image, windows, display_info, area, origin)
+  }
+
+  /// Stamps the watermark and sets the PNG settings, `scale` being image
+  /// pixels per logical pixel.
+  pub(crate) fn finish(
+    &self,
+    image: &mut Image,
+    display_info: &DisplayInfo,
+    scale: f32,
+  ) -> Result<()> {
+    if let Some(watermark) = &self.watermark {
+      watermark.apply(image, display_info, scale)?;
+    }
+
+    image.set_png_options(self.png);
+    Ok(())
+  }
+
+  /// Fills in the metadata, then redacts and masks. `origin` is the position
+  /// of the screen in the coordinates of the rects.
+  fn apply_with_origin(
+    &self,
+    mut image: Image,
+    windows: &[WindowInfo],
+    display_info: &DisplayInfo,
+    area: Rect,
+    origin: (i32, i32),
+  ) -> Result<Image> {
+    let windows = self.window_rects(windows, display_info);
+
+    // 逻辑坐标转换为图像像素坐标，在任何编码之前完成
+    let scale_x = image.width() as f32 / area.width.max(1) as f32;
+    let scale_y = image.height() as f32 / area.height.max(1) as f32;
+
+    let metadata = image.metadata_mut();
+    metadata.timestamp = Some(Utc::now());
+    metadata.display = Some(display_info.into());
+    metadata.area = Some(area);
+    metadata.scale_factor = Some(scale_x);
+    // 读不到显示器配置文件时按 sRGB 处理
+    if image.color_profile().is_none() {
+      image.set_color_profile(Some(ColorProfile::Srgb));
+    }
+
+    let to_screen = |rect: &Rect| {
+      Rect::new(
+        rect.x.saturating_sub(origin.0),
+        rect.y.saturating_sub(origin.1),
+        rect.width,
+        rect.height,
+      )
+    };
+    let to_pixels = |rect: &Rect| {
+      let rect = rect.intersection(&area)?;
+      let x1 = ((rect.x - area.x) as f32 * scale_x).floor() as i32;
+      let y1 = ((rect.y - area.y) as f32 * scale_y).floor() as i32;
+      let x2 = ((rect.right() - area.x) as f32 * scale_x).ceil() as i32;
+      let y2 = ((rect.bottom() - area.y) as f32 * scale_y).ceil() as i32;
+      Some(Rect::new(x1, y1, (x2 - x1) as u32, (y2 - y1) as u32))
+    };
+
+    let redactions = self
+      .redactions
+      .iter()
+      .map(|(rect, style)| (to_screen(rect), *style))
+      .chain(windows.redactions);
+    for (rect, style) in redactions {
+      if let Some(rect) = to_pixels(&rect) {
+        image.redact(rect, style);
+      }
+    }
+
+    if !self.masks.is_empty() || !self.window_masks.is_empty() {
+      let masks: Vec<Rect> = self
+        .masks
+        .iter()
+        .map(to_screen)
+        .chain(windows.masks)
+        .filter_map(|rect| to_pixels(&rect))
+        .collect();
+      image.clear_outside(&masks);
+    }
+
+    Ok(image)
+  }
+}
@@ -0,0 +1,155 @@
+use crate::{CaptureOptions, DisplayInfo, Image, Recorder, Rect};
+use anyhow::{anyhow, Result};
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Settings for `Screen::record`.
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+  /// Target frame rate, frames the capture cannot keep up with are dropped.
+  pub fps: f32,
+  /// Recorded area relative to the screen, like `Screen::capture_area`.
+  /// `None` records the whole screen.
+  pub area: Option<Rect>,
+  /// Stop after this many frames, `None` records until the iterator is dropped.
+  pub max_frames: Option<u64>,
+  /// Applied to every frame, like `Screen::capture_with`.
+  pub capture: CaptureOptions,
+}
+
+impl Default for RecordOptions {
+  fn default() -> Self {
+    RecordOptions {
+      fps: 30.0,
+      area: None,
+      max_frames: None,
+      capture: CaptureOptions::default(),
+    }
+  }
+}
+
+/// A frame of a [`Recording`].
+#[derive(Debug, Clone)]
+pub struct Frame {
+  pub image: Image,
+  /// Time since the first frame, from a monotonic clock.
+  pub timestamp: Duration,
+  /// Position in the frame rate grid, dropped frames leave gaps.
+  pub index: u64,
+  /// Frames dropped right before this one.
+  pub dropped: u64,
+}
+
+/// Iterator over frames captured at a fixed rate, see `Screen::record`. Each
+/// call to `next` waits for the next frame time and captures it, reusing the
+/// backend connection. A failed capture ends the recording after its error
+/// is returned.
+pub struct Recording {
+  recorder: Recorder,
+  display_info: DisplayInfo,
+  area: Rect,
+  options: CaptureOptions,
+  interval: Duration,
+  max_frames: Option<u64>,
+  start: Option<Instant>,
+  next_index: u64,
+  frames: u64,
+  dropped: u64,
+  failed: bool,
+}
+
+impl Recording {
+  pub(crate) fn new(
+    display_info: &DisplayInfo,
+    area: Rect,
+    options: RecordOptions,
+  ) -> Result<Self> {
+    // 帧率极小时间隔会超出 Duration 的范围
+    let interval = Duration::try_from_secs_f64(1.0 / options.fps as f64)
+      .map_err(|_| anyhow!("Invalid frame rate: {}", options.fps))?;
+    let recorder = Recorder::new(display_info, area.x, area.y, area.width, area.height)?;
+
+    Ok(Recording {
+      recorder,
+      display_info: *display_info,
+      area,
+      options: options.capture,
+      interval,
+      max_frames: options.max_frames,
+      start: None,
+      next_index: 0,
+      frames: 0,
+      dropped: 0,
+      failed: false,
+    })
+  }
+
+  /// Number of frames returned so far.
+  pub fn frames(&self) -> u64 {
+    self.frames
+  }
+
+  /// Number of frames dropped so far because capturing, or the consumer of
+  /// the iterator, could not keep up with the frame rate.
+  pub fn dropped_frames(&self) -> u64 {
+    self.dropped
+  }
+
+  fn frame_time(&self, index: u64) -> Duration {
+    let nanos = self.interval.as_nanos() * index as u128;
+    Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+  }
+}
+
+impl Iterator for Recording {
+  type Item = Result<Frame>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.failed || self.max_frames.is_some_and(|max| self.frames >= max) {
+      return None;
+    }
+
+    let start = *self.start.get_or_insert_with(Instant::now);
+    let elapsed = start.elapsed();
+
+    // 按固定节拍采集，已经错过的节拍记为丢帧
+    let current = (elapsed.as_nanos() / self.interval.as_nanos().max(1)) as u64;
+    let index = if current > self.next_index {
+      current
+    } else {
+      thread::sleep(self.frame_time(self.next_index).saturating_sub(elapsed));
+      self.next_index
+    };
+    let dropped = index - self.next_index;
+    self.next_index = index + 1;
+    self.dropped += dropped;
+
+    let timestamp = start.elapsed();
+    let capture = if self.options.needs_windows() {
+      self.recorder.capture_with_windows()
+    } else {
+      self.recorder.capture().map(|image| (image, Vec::new()))
+    };
+    let frame = capture
+      .and_then(|(image, windows)| {
+        self
+          .options
+          .apply(image, &windows, &self.display_info, self.area)
+      })
+      .map(|image| Frame {
+        image,
+        timestamp,
+        index,
+        dropped,
+      });
+
+    // 采集失败通常会一直失败（连接断开、显示器移除），返回一次错误后结束
+    match frame {
+      Ok(_) => self.frames += 1,
+      Err(_) => self.failed = true,
+    }
+    Some(frame)
+  }
+}
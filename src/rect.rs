@@ -0,0 +1,176 @@
+/// An axis-aligned rectangle, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Rect {
+  pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+    Rect {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+
+  /// `x + width`, saturating at `i32::MAX`.
+  pub fn right(&self) -> i32 {
+    self
+      .x
+      .saturating_add(i32::try_from(self.width).unwrap_or(i32::MAX))
+  }
+
+  /// `y + height`, saturating at `i32::MAX`.
+  pub fn bottom(&self) -> i32 {
+    self
+      .y
+      .saturating_add(i32::try_from(self.height).unwrap_or(i32::MAX))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.width == 0 || self.height == 0
+  }
+
+  pub fn area(&self) -> u64 {
+    self.width as u64 * self.height as u64
+  }
+
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+  }
+
+  /// Overlapping part of both rectangles, `None` when they do not overlap.
+  pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+    let x1 = self.x.max(other.x);
+    let y1 = self.y.max(other.y);
+    let x2 = self.right().min(other.right());
+    let y2 = self.bottom().min(other.bottom());
+
+    if x1 >= x2 || y1 >= y2 {
+      return None;
+    }
+
+    Some(Rect::new(x1, y1, x2.abs_diff(x1), y2.abs_diff(y1)))
+  }
+
+  /// Smallest rectangle containing both rectangles.
+  pub fn union(&self, other: &Rect) -> Rect {
+    if self.is_empty() {
+      return *other;
+    }
+    if other.is_empty() {
+      return *self;
+    }
+
+    let x1 = self.x.min(other.x);
+    let y1 = self.y.min(other.y);
+    let x2 = self.right().max(other.right());
+    let y2 = self.bottom().max(other.bottom());
+
+    Rect::new(x1, y1, x2.abs_diff(x1), y2.abs_diff(y1))
+  }
+
+  /// Parts of this rectangle not covered by `other`, at most four.
+  pub fn subtract(&self, other: &Rect) -> Vec<Rect> {
+    let Some(overlap) = self.intersection(other) else {
+      return vec![*self];
+    };
+
+    let mut parts = Vec::with_capacity(4);
+    if overlap.y > self.y {
+      parts.push(Rect::new(
+        self.x,
+        self.y,
+        self.width,
+        overlap.y.abs_diff(self.y),
+      ));
+    }
+    if overlap.bottom() < self.bottom() {
+      parts.push(Rect::new(
+        self.x,
+        overlap.bottom(),
+        self.width,
+        self.bottom().abs_diff(overlap.bottom()),
+      ));
+    }
+    if overlap.x > self.x {
+      parts.push(Rect::new(
+        self.x,
+        overlap.y,
+        overlap.x.abs_diff(self.x),
+        overlap.height,
+      ));
+    }
+    if overlap.right() < self.right() {
+      parts.push(Rect::new(
+        overlap.right(),
+        overlap.y,
+        self.right().abs_diff(overlap.right()),
+        overlap.height,
+      ));
+    }
+    parts
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn area(rects: &[Rect]) -> u64 {
+    rects.iter().map(Rect::area).sum()
+  }
+
+  #[test]
+  fn intersection() {
+    let a = Rect::new(0, 0, 10, 10);
+    assert_eq!(
+      a.intersection(&Rect::new(5, -5, 10, 10)),
+      Some(Rect::new(5, 0, 5, 5))
+    );
+    assert_eq!(
+      a.intersection(&Rect::new(2, 2, 3, 3)),
+      Some(Rect::new(2, 2, 3, 3))
+    );
+    // 只有边相接不算重叠
+    assert_eq!(a.intersection(&Rect::new(10, 0, 5, 5)), None);
+    assert_eq!(a.intersection(&Rect::new(3, 3, 0, 4)), None);
+  }
+
+  #[test]
+  fn edges_saturate() {
+    let rect = Rect::new(i32::MAX - 1, 0, u32::MAX, 1);
+    assert_eq!(rect.right(), i32::MAX);
+    assert_eq!(
+      rect.intersection(&Rect::new(0, 0, u32::MAX, u32::MAX)),
+      Some(Rect::new(i32::MAX - 1, 0, 1, 1))
+    );
+  }
+
+  #[test]
+  fn subtract() {
+    let a = Rect::new(0, 0, 10, 10);
+    assert_eq!(a.subtract(&Rect::new(20, 20, 5, 5)), vec![a]);
+    assert!(a.subtract(&Rect::new(-1, -1, 12, 12)).is_empty());
+
+    // 挖掉中间，剩下上下左右四块，互不重叠且面积守恒
+    let hole = Rect::new(3, 4, 2, 3);
+    let parts = a.subtract(&hole);
+    assert_eq!(parts.len(), 4);
+    assert_eq!(area(&parts), a.area() - hole.area());
+    for (i, part) in parts.iter().enumerate() {
+      assert_eq!(part.intersection(&hole), None);
+      for other in &parts[i + 1..] {
+        assert_eq!(part.intersection(other), None);
+      }
+    }
+
+    let parts = a.subtract(&Rect::new(5, -5, 10, 10));
+    assert_eq!(area(&parts), 75);
+  }
+}
@@ -0,0 +1,154 @@
+//! Golden-image assertions for UI tests.
+//!
+//! Set `SCREENSHOTS_BLESS=1` to write the current captures as the new goldens.
+
+use crate::{Image, ImageFormat, Rect, Screen};
+use anyhow::{anyhow, Result};
+use std::{
+  env, fs,
+  path::{Path, PathBuf},
+};
+
+/// Environment variable that turns golden mismatches into golden updates.
+pub const BLESS_ENV: &str = "SCREENSHOTS_BLESS";
+
+/// Something that can be captured and compared against a golden image.
+pub trait CaptureTarget {
+  fn capture_image(&self) -> Result<Image>;
+}
+
+impl CaptureTarget for Screen {
+  fn capture_image(&self) -> Result<Image> {
+    self.capture()
+  }
+}
+
+/// An area of a screen, relative to the screen.
+impl CaptureTarget for (Screen, Rect) {
+  fn capture_image(&self) -> Result<Image> {
+    let (screen, rect) = self;
+    screen.capture_area(rect.x, rect.y, rect.width, rect.height)
+  }
+}
+
+impl CaptureTarget for Image {
+  fn capture_image(&self) -> Result<Image> {
+    Ok(self.clone())
+  }
+}
+
+impl<T: CaptureTarget + ?Sized> CaptureTarget for &T {
+  fn capture_image(&self) -> Result<Image> {
+    (**self).capture_image()
+  }
+}
+
+fn is_bless() -> bool {
+  env::var_os(BLESS_ENV).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+// 相对路径以被测 crate 的根目录为准
+fn resolve(golden: &Path) -> PathBuf {
+  match env::var_os("CARGO_MANIFEST_DIR") {
+    Some(dir) if golden.is_relative() => Path::new(&dir).join(golden),
+    _ => golden.to_path_buf(),
+  }
+}
+
+fn sibling(golden: &Path, suffix: &str) -> PathBuf {
+  let stem = golden.file_stem().unwrap_or_default().to_string_lossy();
+  golden.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+/// Captures `target` and compares it with the PNG at `golden`. On mismatch
+/// `<name>.actual.png` and `<name>.diff.png` are written next to the golden.
+pub fn check<T: CaptureTarget + ?Sized>(
+  target: &T,
+  golden: impl AsRef<Path>,
+  tolerance: u8,
+) -> Result<()> {
+  let golden = resolve(golden.as_ref());
+  let actual = target.capture_image()?;
+  let actual_path = sibling(&golden, "actual");
+  let diff_path = sibling(&golden, "diff");
+
+  if is_bless() {
+    if let Some(dir) = golden.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    actual.save_with_format(&golden, ImageFormat::Png)?;
+    let _ = fs::remove_file(&actual_path);
+    let _ = fs::remove_file(&diff_path);
+    return Ok(());
+  }
+
+  let expected = match fs::read(&golden) {
+    Ok(buffer) => Image::from_png(&buffer)?,
+    Err(err) => {
+      if let Some(dir) = golden.parent() {
+        fs::create_dir_all(dir)?;
+      }
+      actual.save_with_format(&actual_path, ImageFormat::Png)?;
+      return Err(anyhow!(
+        "Golden {} not readable ({err}), actual written to {}, run with {BLESS_ENV}=1 to accept it",
+        golden.display(),
+        actual_path.display()
+      ));
+    }
+  };
+
+  if expected.width() != actual.width() || expected.height() != actual.height() {
+    actual.save_with_format(&actual_path, ImageFormat::Png)?;
+    return Err(anyhow!(
+      "Size mismatch against {}: expected {}x{}, got {}x{}, actual written to {}",
+      golden.display(),
+      expected.width(),
+      expected.height(),
+      actual.width(),
+      actual.height(),
+      actual_path.display()
+    ));
+  }
+
+  let result = expected.diff(&actual, tolerance)?;
+  if let Some(diff_image) = result.diff_image {
+    actual.save_with_format(&actual_path, ImageFormat::Png)?;
+    diff_image.save_with_format(&diff_path, ImageFormat::Png)?;
+    return Err(anyhow!(
+      "{} pixels differ from {} in {:?}, actual written to {}, diff written to {}",
+      result.changed_pixels,
+      golden.display(),
+      result.regions,
+      actual_path.display(),
+      diff_path.display()
+    ));
+  }
+
+  let _ = fs::remove_file(&actual_path);
+  let _ = fs::remove_file(&diff_path);
+  Ok(())
+}
+
+/// Panicking version of [`check`], used by [`assert_screen_matches!`](crate::assert_screen_matches).
+#[track_caller]
+pub fn assert_matches<T: CaptureTarget + ?Sized>(
+  target: &T,
+  golden: impl AsRef<Path>,
+  tolerance: u8,
+) {
+  if let Err(err) = check(target, golden, tolerance) {
+    panic!("screen does not match golden: {err:#}");
+  }
+}
+
+/// Asserts that a `Screen`, a `(Screen, Rect)` area or an `Image` matches a
+/// golden PNG, allowing each channel to differ by `tolerance` (default `0`).
+#[macro_export]
+macro_rules! assert_screen_matches {
+  ($target:expr, $golden:expr $(,)?) => {
+    $crate::testing::assert_matches(&$target, $golden, 0)
+  };
+  ($target:expr, $golden:expr, $tolerance:expr $(,)?) => {
+    $crate::testing::assert_matches(&$target, $golden, $tolerance)
+  };
+}
@@ -0,0 +1,171 @@
+use crate::{
+  annotate::{self, TextStyle},
+  DisplayInfo, Image,
+};
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use gethostname::gethostname;
+use std::fmt::Write;
+
+/// Corner of the image a [`Watermark`] is anchored to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Corner {
+  TopLeft,
+  TopRight,
+  BottomLeft,
+  #[default]
+  BottomRight,
+}
+
+/// Text and/or an image stamped onto every capture, see
+/// `CaptureOptions::watermark`.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+  text: Option<String>,
+  style: TextStyle,
+  image: Option<Image>,
+  corner: Corner,
+  margin: u32,
+  opacity: f32,
+}
+
+impl Default for Watermark {
+  fn default() -> Self {
+    Watermark {
+      text: None,
+      style: TextStyle::default(),
+      image: None,
+      corner: Corner::default(),
+      margin: 8,
+      opacity: 1.0,
+    }
+  }
+}
+
+impl Watermark {
+  pub fn new() -> Self {
+    Watermark::default()
+  }
+
+  /// Text template, formatted with the local time of the capture using
+  /// strftime specifiers (`%Y-%m-%d %H:%M:%S`). `{host}`, `{display_id}` and
+  /// `{scale}` are replaced with the host name and the captured display's id
+  /// and scale factor. Stitched captures are stamped once, with the first
+  /// screen's id and the output scale.
+  pub fn text(mut self, template: impl Into<String>) -> Self {
+    self.text = Some(template.into());
+    self
+  }
+
+  /// Style of the text, sizes are in logical pixels.
+  pub fn text_style(mut self, style: TextStyle) -> Self {
+    self.style = style;
+    self
+  }
+
+  /// Image drawn next to the text, e.g. a logo. Drawn at its own size.
+  pub fn image(mut self, image: Image) -> Self {
+    self.image = Some(image);
+    self
+  }
+
+  pub fn corner(mut self, corner: Corner) -> Self {
+    self.corner = corner;
+    self
+  }
+
+  /// Distance from the edges of the capture, in logical pixels.
+  pub fn margin(mut self, margin: u32) -> Self {
+    self.margin = margin;
+    self
+  }
+
+  /// Opacity of the whole watermark, `0.0..=1.0`.
+  pub fn opacity(mut self, opacity: f32) -> Self {
+    self.opacity = opacity.clamp(0.0, 1.0);
+    self
+  }
+
+  fn format_text(template: &str, display_info: &DisplayInfo) -> Result<String> {
+    let mut text = String::new();
+    write!(text, "{}", Local::now().format(template))
+      .map_err(|_| anyhow!("Invalid watermark template {template:?}"))?;
+
+    Ok(
+      text
+        .replace("{host}", &gethostname().to_string_lossy())
+        .replace("{display_id}", &display_info.id.to_string())
+        .replace("{scale}", &display_info.scale_factor.to_string()),
+    )
+  }
+
+  /// Stamps the watermark onto `image`, `scale` being image pixels per
+  /// logical pixel.
+  pub(crate) fn apply(
+    &self,
+    image: &mut Image,
+    display_info: &DisplayInfo,
+    scale: f32,
+  ) -> Result<()> {
+    let text = match &self.text {
+      Some(template) => Some(Watermark::format_text(template, display_info)?),
+      None => None,
+    };
+
+    // 字体只能整数倍缩放，HiDPI 下按比例放大
+    let factor = scale.round().max(1.0) as u32;
+    let fade = |color: [u8; 4]| {
+      let alpha = (color[3] as f32 * self.opacity).round() as u8;
+      [color[0], color[1], color[2], alpha]
+    };
+    let style = TextStyle {
+      color: fade(self.style.color),
+      scale: self.style.scale.max(1) * factor,
+      background: self.style.background.map(fade),
+      padding: self.style.padding * factor,
+    };
+    let margin = (self.margin * factor) as i32;
+    let gap = if text.is_some() && self.image.is_some() {
+      margin
+    } else {
+      0
+    };
+
+    let (text_width, text_height) = match &text {
+      Some(text) => {
+        let (width, height) = annotate::text_size(text, style.scale);
+        (width + style.padding * 2, height + style.padding * 2)
+      }
+      None => (0, 0),
+    };
+    let (image_width, image_height) = self
+      .image
+      .as_ref()
+      .map_or((0, 0), |image| (image.width(), image.height()));
+
+    // 图片在左，文字在右，整体贴靠指定的角
+    let width = (image_width + text_width) as i32 + gap;
+    let height = image_height.max(text_height) as i32;
+    let x = match self.corner {
+      Corner::TopLeft | Corner::BottomLeft => margin,
+      Corner::TopRight | Corner::BottomRight => image.width() as i32 - margin - width,
+    };
+    let y = match self.corner {
+      Corner::TopLeft | Corner::TopRight => margin,
+      Corner::BottomLeft | Corner::BottomRight => image.height() as i32 - margin - height,
+    };
+
+    if let Some(overlay) = &self.image {
+      let top = y + (height - image_height as i32) / 2;
+      image.overlay(overlay, x, top, self.opacity);
+    }
+
+    if let Some(text) = text {
+      let left = x + image_width as i32 + gap + style.padding as i32;
+      let top = y + (height - text_height as i32) / 2 + style.padding as i32;
+      annotate::text(image, left, top, &text, &style);
+    }
+
+    Ok(())
+  }
+}
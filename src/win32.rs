@@ -1,10 +1,10 @@
-use crate::{DisplayInfo, Image};
+use crate::{window::WindowInfo, AlphaMode, ColorProfile, DisplayInfo, Image, Rect, WindowRule};
 use anyhow::{anyhow, Result};
 use fxhash::hash32;
-use std::{mem, ops::Deref, ptr};
+use std::{fs, mem, ptr};
 use widestring::U16CString;
 use windows::{
-  core::PCWSTR,
+  core::{PCWSTR, PWSTR},
   Win32::{
     Foundation::{BOOL, LPARAM, RECT},
     Graphics::Gdi::{
@@ -13,32 +13,10 @@ use windows::{
       StretchBlt, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, HBITMAP, HDC, HMONITOR,
       MONITORINFOEXW, RGBQUAD, SRCCOPY, STRETCH_HALFTONE,
     },
+    UI::ColorSystem::GetICMProfileW,
   },
 };
 
-// 自动释放资源
-macro_rules! drop_box {
-  ($type:tt, $value:expr, $drop:expr) => {{
-    struct DropBox($type);
-
-    impl Deref for DropBox {
-      type Target = $type;
-
-      fn deref(&self) -> &Self::Target {
-        &self.0
-      }
-    }
-
-    impl Drop for DropBox {
-      fn drop(&mut self) {
-        $drop(self.0);
-      }
-    }
-
-    DropBox($value)
-  }};
-}
-
 fn get_monitor_info_exw(h_monitor: HMONITOR) -> Result<MONITORINFOEXW> {
   let mut monitor_info_exw: MONITORINFOEXW = unsafe { mem::zeroed() };
   monitor_info_exw.monitorInfo.cbSize = mem::size_of::<MONITORINFOEXW>() as u32;
@@ -95,130 +73,183 @@ extern "system" fn monitor_enum_proc(
   }
 }
 
-fn capture(display_id: u32, x: i32, y: i32, width: i32, height: i32) -> Result<Image> {
-  let monitor_info_exw = get_monitor_info_exw_from_id(display_id)?;
+// 读取设备上下文关联的 ICC 配置文件
+fn icc_profile(hdc: CreatedHDC) -> Option<Vec<u8>> {
+  let mut len = 0u32;
+  unsafe { GetICMProfileW(hdc, &mut len, PWSTR::null()) };
+  if len == 0 {
+    return None;
+  }
 
-  let sz_device = monitor_info_exw.szDevice;
-  let sz_device_ptr = sz_device.as_ptr();
+  let mut buffer = vec![0u16; len as usize];
+  if !unsafe { GetICMProfileW(hdc, &mut len, PWSTR(buffer.as_mut_ptr())) }.as_bool() {
+    return None;
+  }
 
-  let dcw_drop_box = drop_box!(
-    CreatedHDC,
+  let path = U16CString::from_vec_truncate(buffer).to_os_string();
+  fs::read(path).ok()
+}
+
+/// Device contexts, bitmap and pixel buffer kept between captures of the
+/// same area.
+pub struct Recorder {
+  dcw: CreatedHDC,
+  compatible_dc: CreatedHDC,
+  h_bitmap: HBITMAP,
+  profile: Option<ColorProfile>,
+  data: Vec<u8>,
+  x: i32,
+  y: i32,
+  width: i32,
+  height: i32,
+}
+
+impl Drop for Recorder {
+  fn drop(&mut self) {
     unsafe {
+      DeleteObject(self.h_bitmap);
+      DeleteDC(self.compatible_dc);
+      DeleteDC(self.dcw);
+    }
+  }
+}
+
+impl Recorder {
+  fn connect(display_id: u32, x: i32, y: i32, width: i32, height: i32) -> Result<Self> {
+    let monitor_info_exw = get_monitor_info_exw_from_id(display_id)?;
+
+    let sz_device = monitor_info_exw.szDevice;
+    let sz_device_ptr = sz_device.as_ptr();
+
+    let dcw = unsafe {
       CreateDCW(
         PCWSTR(sz_device_ptr),
         PCWSTR(sz_device_ptr),
         PCWSTR(ptr::null()),
         None,
       )
-    },
-    |dcw| unsafe { DeleteDC(dcw) }
-  );
-
-  let compatible_dc_drop_box = drop_box!(
-    CreatedHDC,
-    unsafe { CreateCompatibleDC(*dcw_drop_box) },
-    |compatible_dc| unsafe { DeleteDC(compatible_dc) }
-  );
-
-  let h_bitmap_drop_box = drop_box!(
-    HBITMAP,
-    unsafe { CreateCompatibleBitmap(*dcw_drop_box, width, height) },
-    |h_bitmap| unsafe { DeleteObject(h_bitmap) }
-  );
-
-  unsafe {
-    SelectObject(*compatible_dc_drop_box, *h_bitmap_drop_box);
-    SetStretchBltMode(*dcw_drop_box, STRETCH_HALFTONE);
-  };
+    };
+    let compatible_dc = unsafe { CreateCompatibleDC(dcw) };
+    let h_bitmap = unsafe { CreateCompatibleBitmap(dcw, width, height) };
 
-  unsafe {
-    StretchBlt(
-      *compatible_dc_drop_box,
-      0,
-      0,
-      width,
-      height,
-      *dcw_drop_box,
+    unsafe {
+      SelectObject(compatible_dc, h_bitmap);
+      SetStretchBltMode(dcw, STRETCH_HALFTONE);
+    };
+
+    Ok(Recorder {
+      dcw,
+      compatible_dc,
+      h_bitmap,
+      profile: icc_profile(dcw).map(ColorProfile::Icc),
+      data: vec![0u8; (width * height) as usize * 4],
       x,
       y,
       width,
       height,
-      SRCCOPY,
-    )
-    .ok()?;
-  };
-
-  let mut bitmap_info = BITMAPINFO {
-    bmiHeader: BITMAPINFOHEADER {
-      biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
-      biWidth: width,
-      biHeight: height, // 这里可以传递负数, 但是不知道为什么会报错
-      biPlanes: 1,
-      biBitCount: 32,
-      biCompression: 0,
-      biSizeImage: 0,
-      biXPelsPerMeter: 0,
-      biYPelsPerMeter: 0,
-      biClrUsed: 0,
-      biClrImportant: 0,
-    },
-    bmiColors: [RGBQUAD::default(); 1],
-  };
-
-  let data = vec![0u8; (width * height) as usize * 4];
-  let buf_prt = data.as_ptr() as *mut _;
-
-  let is_success = unsafe {
-    GetDIBits(
-      *compatible_dc_drop_box,
-      *h_bitmap_drop_box,
-      0,
-      height as u32,
-      Some(buf_prt),
-      &mut bitmap_info,
-      DIB_RGB_COLORS,
-    ) == 0
-  };
-
-  if is_success {
-    return Err(anyhow!("Get RGBA data failed"));
+    })
   }
 
-  let mut bitmap = BITMAP::default();
-  let bitmap_ptr = <*mut _>::cast(&mut bitmap);
+  /// `x`, `y`, `width` and `height` are logical and relative to the display,
+  /// like `capture_screen_area`.
+  pub fn new(display_info: &DisplayInfo, x: i32, y: i32, width: u32, height: u32) -> Result<Self> {
+    let area_x = ((x as f32) * display_info.scale_factor) as i32;
+    let area_y = ((y as f32) * display_info.scale_factor) as i32;
+    let area_width = ((width as f32) * display_info.scale_factor) as i32;
+    let area_height = ((height as f32) * display_info.scale_factor) as i32;
 
-  unsafe {
-    // Get the BITMAP from the HBITMAP.
-    GetObjectW(
-      *h_bitmap_drop_box,
-      mem::size_of::<BITMAP>() as i32,
-      Some(bitmap_ptr),
-    );
+    Recorder::connect(display_info.id, area_x, area_y, area_width, area_height)
   }
 
-  // 旋转图像,图像数据是倒置的
-  let mut chunks: Vec<Vec<u8>> = data
-    .chunks(width as usize * 4)
-    .map(|x| x.to_vec())
-    .collect();
+  pub fn capture(&mut self) -> Result<Image> {
+    unsafe {
+      StretchBlt(
+        self.compatible_dc,
+        0,
+        0,
+        self.width,
+        self.height,
+        self.dcw,
+        self.x,
+        self.y,
+        self.width,
+        self.height,
+        SRCCOPY,
+      )
+      .ok()?;
+    };
+
+    let mut bitmap_info = BITMAPINFO {
+      bmiHeader: BITMAPINFOHEADER {
+        biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+        biWidth: self.width,
+        biHeight: self.height, // 这里可以传递负数, 但是不知道为什么会报错
+        biPlanes: 1,
+        biBitCount: 32,
+        biCompression: 0,
+        biSizeImage: 0,
+        biXPelsPerMeter: 0,
+        biYPelsPerMeter: 0,
+        biClrUsed: 0,
+        biClrImportant: 0,
+      },
+      bmiColors: [RGBQUAD::default(); 1],
+    };
+
+    let buf_prt = self.data.as_mut_ptr() as *mut _;
+
+    let is_success = unsafe {
+      GetDIBits(
+        self.compatible_dc,
+        self.h_bitmap,
+        0,
+        self.height as u32,
+        Some(buf_prt),
+        &mut bitmap_info,
+        DIB_RGB_COLORS,
+      ) == 0
+    };
+
+    if is_success {
+      return Err(anyhow!("Get RGBA data failed"));
+    }
 
-  chunks.reverse();
+    let mut bitmap = BITMAP::default();
+    let bitmap_ptr = <*mut _>::cast(&mut bitmap);
 
-  let image = Image::from_bgra(
-    chunks.concat(),
-    bitmap.bmWidth as u32,
-    bitmap.bmHeight as u32,
-    bitmap.bmWidthBytes as usize,
-  )?;
+    unsafe {
+      // Get the BITMAP from the HBITMAP.
+      GetObjectW(
+        self.h_bitmap,
+        mem::size_of::<BITMAP>() as i32,
+        Some(bitmap_ptr),
+      );
+    }
 
-  Ok(image)
+    let mut image = Image::from_bgra_slice(
+      &self.data,
+      bitmap.bmWidth as u32,
+      bitmap.bmHeight as u32,
+      bitmap.bmWidthBytes as usize,
+      AlphaMode::Ignore,
+    )?;
+    image.metadata_mut().backend = Some("gdi".to_string());
+    image.set_color_profile(self.profile.clone());
+
+    // 图像数据是倒置的
+    Ok(image.flip_vertical())
+  }
+
+  pub fn capture_with_windows(&mut self) -> Result<(Image, Vec<WindowInfo>)> {
+    Err(anyhow!("Listing windows is not supported on this platform"))
+  }
 }
 
 pub fn capture_screen(display_info: &DisplayInfo) -> Result<Image> {
   let width = ((display_info.width as f32) * display_info.scale_factor) as i32;
   let height = ((display_info.height as f32) * display_info.scale_factor) as i32;
 
-  capture(display_info.id, 0, 0, width, height)
+  Recorder::connect(display_info.id, 0, 0, width, height)?.capture()
 }
 
 pub fn capture_screen_area(
@@ -228,10 +259,24 @@ pub fn capture_screen_area(
   width: u32,
   height: u32,
 ) -> Result<Image> {
-  let area_x = ((x as f32) * display_info.scale_factor) as i32;
-  let area_y = ((y as f32) * display_info.scale_factor) as i32;
-  let area_width = ((width as f32) * display_info.scale_factor) as i32;
-  let area_height = ((height as f32) * display_info.scale_factor) as i32;
+  Recorder::new(display_info, x, y, width, height)?.capture()
+}
+
+pub fn capture_screen_area_with_windows(
+  _display_info: &DisplayInfo,
+  _x: i32,
+  _y: i32,
+  _width: u32,
+  _height: u32,
+) -> Result<(Image, Vec<WindowInfo>)> {
+  Err(anyhow!("Listing windows is not supported on this platform"))
+}
 
-  capture(display_info.id, area_x, area_y, area_width, area_height)
+pub fn capture_raw_window(
+  _rule: &WindowRule,
+  _keep_alpha: bool,
+) -> Result<(Image, Rect, Vec<WindowInfo>)> {
+  Err(anyhow!(
+    "Capturing windows is not supported on this platform"
+  ))
 }
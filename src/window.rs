@@ -0,0 +1,119 @@
+use crate::{capture_raw_window, CaptureOptions, DisplayInfo, Image, Rect};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+
+/// Selects top-level windows for redaction. `class` and `title` are
+/// case-insensitive substring searches: `WindowRule::title("bank")` matches
+/// "Online Banking". `class_regex` and `title_regex` match a regular
+/// expression anywhere in the text, e.g. `WindowRule::title_regex("(?i)^bank")`.
+#[derive(Debug, Clone)]
+pub enum WindowRule {
+  /// Matches either part of `WM_CLASS` (instance or class name).
+  Class(String),
+  /// Matches the window title.
+  Title(String),
+  /// Matches either part of `WM_CLASS` against a regular expression.
+  ClassRegex(Regex),
+  /// Matches the window title against a regular expression.
+  TitleRegex(Regex),
+}
+
+impl PartialEq for WindowRule {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (WindowRule::Class(a), WindowRule::Class(b))
+      | (WindowRule::Title(a), WindowRule::Title(b)) => a == b,
+      (WindowRule::ClassRegex(a), WindowRule::ClassRegex(b))
+      | (WindowRule::TitleRegex(a), WindowRule::TitleRegex(b)) => a.as_str() == b.as_str(),
+      _ => false,
+    }
+  }
+}
+
+impl Eq for WindowRule {}
+
+impl WindowRule {
+  pub fn class(class: impl Into<String>) -> Self {
+    WindowRule::Class(class.into())
+  }
+
+  pub fn title(title: impl Into<String>) -> Self {
+    WindowRule::Title(title.into())
+  }
+
+  /// Fails when `pattern` is not a valid regular expression.
+  pub fn class_regex(pattern: &str) -> Result<Self> {
+    Ok(WindowRule::ClassRegex(Regex::new(pattern)?))
+  }
+
+  /// Fails when `pattern` is not a valid regular expression.
+  pub fn title_regex(pattern: &str) -> Result<Self> {
+    Ok(WindowRule::TitleRegex(Regex::new(pattern)?))
+  }
+
+  pub(crate) fn matches(&self, window: &WindowInfo) -> bool {
+    match self {
+      WindowRule::Class(pattern) => {
+        let pattern = pattern.to_lowercase();
+        window
+          .classes
+          .iter()
+          .any(|class| class.to_lowercase().contains(&pattern))
+      }
+      WindowRule::Title(pattern) => window
+        .title
+        .to_lowercase()
+        .contains(&pattern.to_lowercase()),
+      WindowRule::ClassRegex(regex) => window.classes.iter().any(|class| regex.is_match(class)),
+      WindowRule::TitleRegex(regex) => regex.is_match(&window.title),
+    }
+  }
+}
+
+/// A top-level window as reported by the platform backend.
+pub(crate) struct WindowInfo {
+  pub classes: Vec<String>,
+  pub title: String,
+  /// Parts not covered by opaque windows stacked above, in physical pixels
+  /// of the global desktop.
+  pub visible: Vec<crate::Rect>,
+}
+
+/// Captures the topmost window matching `rule`, including the parts covered
+/// by other windows. X11 only; the content is only complete when the window
+/// is redirected, e.g. by a compositing manager.
+pub fn capture_window(rule: &WindowRule) -> Result<Image> {
+  capture_window_with(rule, &CaptureOptions::default())
+}
+
+/// Same as [`capture_window`]. Rects in `options` are relative to the screen
+/// containing the window's center, like `Screen::capture_area`.
+pub fn capture_window_with(rule: &WindowRule, options: &CaptureOptions) -> Result<Image> {
+  let (image, rect, windows) = capture_raw_window(rule, options.keeps_alpha())?;
+
+  // 窗口坐标为物理像素的全局坐标，按窗口中心所在的屏幕转换为相对屏幕的逻辑坐标
+  let center_x = rect.x.saturating_add((rect.width / 2) as i32);
+  let center_y = rect.y.saturating_add((rect.height / 2) as i32);
+  let display_info = DisplayInfo::all()?
+    .into_iter()
+    .find(|info| {
+      let scale = info.scale_factor;
+      Rect::new(
+        (info.x as f32 * scale) as i32,
+        (info.y as f32 * scale) as i32,
+        (info.width as f32 * scale) as u32,
+        (info.height as f32 * scale) as u32,
+      )
+      .contains(center_x, center_y)
+    })
+    .ok_or_else(|| anyhow!("Window is not on any screen"))?;
+
+  let scale = display_info.scale_factor;
+  let x1 = (rect.x as f32 / scale).floor() as i32 - display_info.x;
+  let y1 = (rect.y as f32 / scale).floor() as i32 - display_info.y;
+  let x2 = (rect.right() as f32 / scale).ceil() as i32 - display_info.x;
+  let y2 = (rect.bottom() as f32 / scale).ceil() as i32 - display_info.y;
+  let area = Rect::new(x1, y1, x2.abs_diff(x1), y2.abs_diff(y1));
+
+  options.apply(image, &windows, &display_info, area)
+}
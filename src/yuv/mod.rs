@@ -0,0 +1,293 @@
+//! Conversion of captures to 8-bit YUV 4:2:0 for video encoders.
+//!
+//! Chroma is the average of each 2x2 block, centered between the luma
+//! samples (JPEG siting). Odd sizes round the chroma planes up. Alpha is
+//! ignored.
+
+mod y4m;
+
+pub use y4m::Y4mWriter;
+
+use crate::Image;
+use anyhow::{anyhow, Result};
+
+/// Color matrix used to derive luma and chroma from RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Matrix {
+  /// SD video, the default of most decoders when nothing is signalled.
+  #[default]
+  Bt601,
+  /// HD video.
+  Bt709,
+}
+
+/// Range of the YUV samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Range {
+  /// Y in `16..=235`, U and V in `16..=240`, as expected by video encoders.
+  #[default]
+  Limited,
+  /// All samples in `0..=255`.
+  Full,
+}
+
+/// Memory layout of the planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+  /// Y plane followed by the U and V planes.
+  #[default]
+  I420,
+  /// Y plane followed by one plane of interleaved U and V.
+  Nv12,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct YuvFormat {
+  pub layout: Layout,
+  pub matrix: Matrix,
+  pub range: Range,
+}
+
+/// Size in bytes of a 4:2:0 frame, the same for both layouts.
+pub fn frame_size(width: u32, height: u32) -> usize {
+  let (width, height) = (width as usize, height as usize);
+  width * height + width.div_ceil(2) * height.div_ceil(2) * 2
+}
+
+/// Converts BGRA pixels, e.g. straight from a capture backend, with rows
+/// `bytes_per_row` apart.
+pub fn from_bgra(
+  bgra: &[u8],
+  width: u32,
+  height: u32,
+  bytes_per_row: usize,
+  format: YuvFormat,
+) -> Result<Vec<u8>> {
+  let (u_width, u_height) = (width as usize, height as usize);
+  if u_height > 0 && bgra.len() < bytes_per_row * (u_height - 1) + u_width * 4 {
+    return Err(anyhow!(
+      "BGRA buffer too small: {} bytes for {}x{} with {} bytes per row",
+      bgra.len(),
+      width,
+      height,
+      bytes_per_row
+    ));
+  }
+
+  let mut buffer = Vec::new();
+  convert(
+    &mut buffer,
+    bgra,
+    width,
+    height,
+    bytes_per_row,
+    [2, 1, 0],
+    format,
+  );
+  Ok(buffer)
+}
+
+/// Fixed point coefficients with 16 fractional bits.
+struct Coefficients {
+  y: [i32; 3],
+  u: [i32; 3],
+  v: [i32; 3],
+  y_offset: i32,
+}
+
+impl Coefficients {
+  fn new(matrix: Matrix, range: Range) -> Self {
+    let (kr, kb) = match matrix {
+      Matrix::Bt601 => (0.299, 0.114),
+      Matrix::Bt709 => (0.2126, 0.0722),
+    };
+    let kg = 1.0 - kr - kb;
+    let (y_scale, c_scale, y_offset) = match range {
+      Range::Limited => (219.0 / 255.0, 224.0 / 255.0, 16),
+      Range::Full => (1.0, 1.0, 0),
+    };
+
+    let fixed = |values: [f64; 3], scale: f64| values.map(|v| (v * scale * 65536.0).round() as i32);
+    Coefficients {
+      y: fixed([kr, kg, kb], y_scale),
+      u: fixed(
+        [-kr / (2.0 * (1.0 - kb)), -kg / (2.0 * (1.0 - kb)), 0.5],
+        c_scale,
+      ),
+      v: fixed(
+        [0.5, -kg / (2.0 * (1.0 - kr)), -kb / (2.0 * (1.0 - kr))],
+        c_scale,
+      ),
+      y_offset,
+    }
+  }
+}
+
+fn apply(c: &[i32; 3], rgb: [i32; 3], offset: i32) -> u8 {
+  let value = (c[0] * rgb[0] + c[1] * rgb[1] + c[2] * rgb[2] + (1 << 15)) >> 16;
+  (value + offset).clamp(0, 255) as u8
+}
+
+/// Converts into `buffer`, reusing its allocation. `order` holds the byte
+/// offsets of red, green and blue within a pixel.
+pub(crate) fn convert(
+  buffer: &mut Vec<u8>,
+  pixels: &[u8],
+  width: u32,
+  height: u32,
+  stride: usize,
+  order: [usize; 3],
+  format: YuvFormat,
+) {
+  let (width, height) = (width as usize, height as usize);
+  let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+  let c = Coefficients::new(format.matrix, format.range);
+
+  buffer.clear();
+  buffer.resize(frame_size(width as u32, height as u32), 0);
+  let (luma, chroma) = buffer.split_at_mut(width * height);
+  let plane_size = chroma_width * chroma_height;
+
+  let rgb = |x: usize, y: usize| {
+    let i = y * stride + x * 4;
+    order.map(|offset| pixels[i + offset] as i32)
+  };
+
+  for y in 0..height {
+    for x in 0..width {
+      luma[y * width + x] = apply(&c.y, rgb(x, y), c.y_offset);
+    }
+  }
+
+  for cy in 0..chroma_height {
+    for cx in 0..chroma_width {
+      // 2x2 块取平均，奇数边缘重复最后一行/列
+      let (x1, y1) = (cx * 2, cy * 2);
+      let (x2, y2) = ((x1 + 1).min(width - 1), (y1 + 1).min(height - 1));
+      let mut sum = [0; 3];
+      for (x, y) in [(x1, y1), (x2, y1), (x1, y2), (x2, y2)] {
+        let p = rgb(x, y);
+        (0..3).for_each(|i| sum[i] += p[i]);
+      }
+      let average = sum.map(|v| (v + 2) / 4);
+
+      let u = apply(&c.u, average, 128);
+      let v = apply(&c.v, average, 128);
+      let i = cy * chroma_width + cx;
+      match format.layout {
+        Layout::I420 => {
+          chroma[i] = u;
+          chroma[plane_size + i] = v;
+        }
+        Layout::Nv12 => {
+          chroma[i * 2] = u;
+          chroma[i * 2 + 1] = v;
+        }
+      }
+    }
+  }
+}
+
+impl Image {
+  /// Converts the image to YUV 4:2:0 in the given format.
+  pub fn to_yuv(&self, format: YuvFormat) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    convert(
+      &mut buffer,
+      self.as_raw(),
+      self.width(),
+      self.height(),
+      self.stride(),
+      [0, 1, 2],
+      format,
+    );
+    buffer
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn solid(rgb: [u8; 3]) -> Image {
+    let buffer = (0..3 * 3).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]);
+    Image::new(3, 3, buffer.collect())
+  }
+
+  /// Converts `image` with every matrix and layout.
+  fn levels(image: &Image, range: Range) -> Vec<(Layout, Vec<u8>)> {
+    let mut frames = Vec::new();
+    for matrix in [Matrix::Bt601, Matrix::Bt709] {
+      for layout in [Layout::I420, Layout::Nv12] {
+        let format = YuvFormat {
+          layout,
+          matrix,
+          range,
+        };
+        frames.push((layout, image.to_yuv(format)));
+      }
+    }
+    frames
+  }
+
+  fn assert_planes(frame: &[u8], y: u8, uv: u8) {
+    // 3x3 的图片有 9 个亮度样本和 2x2 的色度平面
+    assert_eq!(frame.len(), frame_size(3, 3));
+    assert!(frame[..9].iter().all(|&v| v == y), "{frame:?}");
+    assert!(frame[9..].iter().all(|&v| v == uv), "{frame:?}");
+  }
+
+  #[test]
+  fn limited_range() {
+    for (_, frame) in levels(&solid([255, 255, 255]), Range::Limited) {
+      assert_planes(&frame, 235, 128);
+    }
+    for (_, frame) in levels(&solid([0, 0, 0]), Range::Limited) {
+      assert_planes(&frame, 16, 128);
+    }
+  }
+
+  #[test]
+  fn full_range() {
+    for (_, frame) in levels(&solid([255, 255, 255]), Range::Full) {
+      assert_planes(&frame, 255, 128);
+    }
+    for (_, frame) in levels(&solid([0, 0, 0]), Range::Full) {
+      assert_planes(&frame, 0, 128);
+    }
+  }
+
+  #[test]
+  fn chroma_extremes() {
+    // 纯红的 V 与纯蓝的 U 达到色度范围的上限
+    for (range, max) in [(Range::Limited, 240), (Range::Full, 255)] {
+      for (layout, frame) in levels(&solid([255, 0, 0]), range) {
+        let v = if layout == Layout::I420 {
+          frame[13]
+        } else {
+          frame[10]
+        };
+        assert_eq!(v, max);
+      }
+      for (_, frame) in levels(&solid([0, 0, 255]), range) {
+        assert_eq!(frame[9], max);
+      }
+    }
+  }
+
+  #[test]
+  fn bgra_matches_rgba() {
+    let image = solid([200, 100, 50]);
+    let bgra: Vec<u8> = image
+      .as_raw()
+      .chunks_exact(4)
+      .flat_map(|p| [p[2], p[1], p[0], p[3]])
+      .collect();
+    let format = YuvFormat::default();
+    assert_eq!(
+      from_bgra(&bgra, 3, 3, 12, format).unwrap(),
+      image.to_yuv(format)
+    );
+    assert!(from_bgra(&bgra[1..], 3, 3, 12, format).is_err());
+  }
+}
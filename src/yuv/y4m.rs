@@ -0,0 +1,125 @@
+use super::{convert, Layout, Matrix, Range, YuvFormat};
+use crate::{Frame, Image};
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+/// Writes frames as a YUV4MPEG2 stream (`C420jpeg`), which `ffmpeg -i -`
+/// reads from a pipe. The header is written with the first frame, so the
+/// size does not have to be known up front.
+///
+/// Y4M has no field for the color matrix, pass the matching `-colorspace`
+/// (`bt470bg` or `bt709`) to ffmpeg. The range is written as the
+/// `XCOLORRANGE` extension.
+pub struct Y4mWriter<W: Write> {
+  w: W,
+  frame_rate: (u32, u32),
+  format: YuvFormat,
+  size: Option<(u32, u32)>,
+  buffer: Vec<u8>,
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+  if b == 0 {
+    a
+  } else {
+    gcd(b, a % b)
+  }
+}
+
+impl<W: Write> Y4mWriter<W> {
+  /// `fps` is kept to three decimals and must be between `0.001` and
+  /// `1000000`.
+  pub fn new(w: W, fps: f32, matrix: Matrix, range: Range) -> Result<Self> {
+    // 帧率以分数表示，保留三位小数，如 29.97 写为 2997:100
+    let numerator = (fps as f64 * 1000.0).round();
+    if !(fps.is_finite() && numerator >= 1.0 && fps <= 1_000_000.0) {
+      return Err(anyhow!("Invalid frame rate: {}", fps));
+    }
+    let numerator = numerator as u32;
+    let divisor = gcd(numerator, 1000);
+
+    Ok(Y4mWriter {
+      w,
+      frame_rate: (numerator / divisor, 1000 / divisor),
+      format: YuvFormat {
+        layout: Layout::I420,
+        matrix,
+        range,
+      },
+      size: None,
+      buffer: Vec::new(),
+    })
+  }
+
+  pub fn write_frame(&mut self, image: &Image) -> Result<()> {
+    let (width, height) = (image.width(), image.height());
+    match self.size {
+      None => {
+        let range = match self.format.range {
+          Range::Limited => "LIMITED",
+          Range::Full => "FULL",
+        };
+        writeln!(
+          self.w,
+          "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg XCOLORRANGE={}",
+          width, height, self.frame_rate.0, self.frame_rate.1, range
+        )?;
+        self.size = Some((width, height));
+      }
+      Some(size) if size != (width, height) => {
+        return Err(anyhow!(
+          "Frame size mismatch: {}x{} and {}x{}",
+          size.0,
+          size.1,
+          width,
+          height
+        ));
+      }
+      Some(_) => {}
+    }
+
+    convert(
+      &mut self.buffer,
+      image.as_raw(),
+      width,
+      height,
+      image.stride(),
+      [0, 1, 2],
+      self.format,
+    );
+    self.write_buffer()
+  }
+
+  fn write_buffer(&mut self) -> Result<()> {
+    self.w.write_all(b"FRAME\n")?;
+    self.w.write_all(&self.buffer)?;
+    Ok(())
+  }
+
+  /// Writes every frame of `frames`, e.g. a `Recording`, repeating the
+  /// previous frame in place of dropped ones so the stream keeps its frame
+  /// rate. Stops at the first error.
+  pub fn write_recording<I: IntoIterator<Item = Result<Frame>>>(
+    &mut self,
+    frames: I,
+  ) -> Result<()> {
+    for frame in frames {
+      let frame = frame?;
+      if self.size.is_some() {
+        for _ in 0..frame.dropped {
+          self.write_buffer()?;
+        }
+      }
+      self.write_frame(&frame.image)?;
+    }
+
+    self.w.flush()?;
+    Ok(())
+  }
+
+  /// Flushes the stream and returns the writer.
+  pub fn into_inner(mut self) -> Result<W> {
+    self.w.flush()?;
+    Ok(self.w)
+  }
+}